@@ -0,0 +1,103 @@
+//! blending whole buffers of colors together, like layers in an image editor
+
+use crate::Vec3;
+
+/// How a layer's colors are combined with the colors under it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// The layer covers what's under it.
+    #[default]
+    Normal,
+    /// The colors are added, which only ever brightens.
+    Add,
+    /// The colors are multiplied, which only ever darkens: white leaves the other color as it
+    /// is, and black gives black.
+    Multiply,
+    /// The inverted colors are multiplied and inverted back, which only ever brightens: black
+    /// leaves the other color as it is, and white gives white.
+    Screen,
+    /// Multiply where the color under is dark and screen where it's light, for more contrast.
+    Overlay,
+}
+
+impl BlendMode {
+    /// Blend `top` over `base`.
+    pub fn apply(self, base: Vec3, top: Vec3) -> Vec3 {
+        /// Overlay one channel.
+        fn overlay(base: f64, top: f64) -> f64 {
+            if base < 0.5 {
+                2.0 * base * top
+            } else {
+                1.0 - 2.0 * (1.0 - base) * (1.0 - top)
+            }
+        }
+
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        match self {
+            Self::Normal => top,
+            Self::Add => base + top,
+            Self::Multiply => base * top,
+            Self::Screen => white - (white - base) * (white - top),
+            Self::Overlay => Vec3::new(
+                overlay(base.r(), top.r()),
+                overlay(base.g(), top.g()),
+                overlay(base.b(), top.b()),
+            ),
+        }
+    }
+}
+
+/// Blend each color of `src` over the same one in `dst` with `mode`, e.g. to composite two
+/// rendered outputs.
+///
+/// # Panics
+/// If `dst` and `src` have different lengths.
+pub fn blend_buffers(dst: &mut [Vec3], src: &[Vec3], mode: BlendMode) {
+    assert_eq!(
+        dst.len(),
+        src.len(),
+        "`dst` and `src` must have the same length"
+    );
+    for (base, &top) in dst.iter_mut().zip(src) {
+        (*base) = mode.apply(*base, top);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_mode_on_known_colors() {
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        let black = Vec3::new(0.0, 0.0, 0.0);
+        let red = Vec3::new(1.0, 0.0, 0.0);
+        let blended = |mode, base, top| {
+            let mut dst = [base];
+            blend_buffers(&mut dst, &[top], mode);
+            dst[0]
+        };
+        assert_eq!(blended(BlendMode::Normal, white, red), red);
+        assert_eq!(
+            blended(BlendMode::Add, red, Vec3::new(0.5, 0.5, 0.0)),
+            Vec3::new(1.5, 0.5, 0.0)
+        );
+        assert_eq!(blended(BlendMode::Multiply, white, red), red);
+        assert_eq!(blended(BlendMode::Multiply, black, red), black);
+        assert_eq!(blended(BlendMode::Screen, black, red), red);
+        assert_eq!(blended(BlendMode::Screen, white, red), white);
+        // multiplied under the dark red channel, screened under the light green and blue ones
+        let overlay = blended(
+            BlendMode::Overlay,
+            Vec3::new(0.25, 0.75, 0.5),
+            Vec3::new(0.2, 0.2, 0.2),
+        );
+        assert!((overlay - Vec3::new(0.1, 0.6, 0.2)).length() < 1e-12);
+    }
+
+    #[test]
+    #[should_panic]
+    fn different_lengths_panic() {
+        blend_buffers(&mut [Vec3::new(0.0, 0.0, 0.0)], &[], BlendMode::Normal);
+    }
+}
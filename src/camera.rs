@@ -0,0 +1,168 @@
+//! the camera for rendering 3D triangles
+
+use crate::{Mat4, Vec2, Vec3};
+
+/// A perspective camera.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    /// Where the camera is.
+    pub position: Vec3,
+    /// The point the camera looks at.
+    pub target: Vec3,
+    /// Which way is up.
+    pub up: Vec3,
+    /// The vertical field of view in radians.
+    pub fov_y: f64,
+    /// The distance to the near clipping plane.
+    pub near: f64,
+    /// The distance to the far clipping plane.
+    pub far: f64,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            position: Vec3::new(0.0, 0.0, 5.0),
+            target: Vec3::new(0.0, 0.0, 0.0),
+            up: Vec3::new(0.0, 1.0, 0.0),
+            fov_y: std::f64::consts::FRAC_PI_3,
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+}
+
+impl Camera {
+    /// A camera at `eye` looking at `target`, with `up` pointing (roughly) up on the screen, and
+    /// the default field of view and clipping planes.
+    pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        Self {
+            position: eye,
+            target,
+            up,
+            ..Self::default()
+        }
+    }
+    /// The transform from world space to camera space (right-handed, looking down -Z). If the
+    /// camera is at its target it looks down -Z, and if `up` is along the view direction (or
+    /// zero) some other direction perpendicular to it is used as up.
+    pub fn view_matrix(&self) -> Mat4 {
+        let forward = self.forward();
+        let mut right = forward.cross(self.up).normalize();
+        if right.length() == 0.0 || !right.is_finite() {
+            (right, _) = forward.orthonormal_basis();
+        }
+        let up = right.cross(forward);
+
+        Mat4 {
+            rows: [
+                [right.x(), right.y(), right.z(), -right.dot(self.position)],
+                [up.x(), up.y(), up.z(), -up.dot(self.position)],
+                [
+                    -forward.x(),
+                    -forward.y(),
+                    -forward.z(),
+                    forward.dot(self.position),
+                ],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+    /// The unit direction the camera looks in, down -Z if it's at its target.
+    fn forward(&self) -> Vec3 {
+        let forward = (self.target - self.position).normalize();
+        if forward.length() == 0.0 || !forward.is_finite() {
+            Vec3::new(0.0, 0.0, -1.0)
+        } else {
+            forward
+        }
+    }
+    /// The ray through `pixel` on a `width` by `height` output, for picking in 3D: the point on
+    /// the near plane it goes through, and its unit direction away from the camera. If the
+    /// projection can't be undone (e.g. for an empty output), the ray goes from the camera's
+    /// position along the view direction.
+    pub fn screen_to_ray(&self, pixel: Vec2, width: usize, height: usize) -> (Vec3, Vec3) {
+        let (width, height) = (width as f64, height as f64);
+        let inverse = (self.projection(width / height, 0.0, 1.0) * self.view_matrix()).inverse();
+        let Some(inverse) = inverse else {
+            return (self.position, self.forward());
+        };
+        // the opposite of projecting onto the screen
+        let (x, y) = (pixel.x / width * 2.0 - 1.0, 1.0 - pixel.y / height * 2.0);
+        let near = inverse.transform_point(Vec3::new(x, y, 0.0));
+        let far = inverse.transform_point(Vec3::new(x, y, 1.0));
+        let direction = (far - near).normalize();
+        if !near.is_finite() || !direction.is_finite() {
+            return (self.position, self.forward());
+        }
+        (near, direction)
+    }
+    /// The projection for an output with the given aspect ratio (width / height), mapping the
+    /// near plane to `depth_near` and the far plane to `depth_far`.
+    pub fn projection(&self, aspect: f64, depth_near: f64, depth_far: f64) -> Mat4 {
+        Mat4::perspective(
+            self.fov_y, aspect, self.near, self.far, depth_near, depth_far,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_projects_to_the_center() {
+        let camera = Camera::look_at(
+            Vec3::new(3.0, 4.0, 5.0),
+            Vec3::new(1.0, -1.0, 2.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        let view = camera.view_matrix();
+        let in_view = view.transform_point(camera.target);
+        assert!(in_view.x().abs() < 1e-12 && in_view.y().abs() < 1e-12);
+        assert!((in_view.z() + (camera.target - camera.position).length()).abs() < 1e-12);
+        let projected = (camera.projection(1.5, 0.0, 1.0) * view).transform_point(camera.target);
+        assert!(projected.x().abs() < 1e-12 && projected.y().abs() < 1e-12);
+
+        // at its target, or with up along the view, it still gives a usable matrix
+        let stuck = Camera::look_at(camera.target, camera.target, Vec3::new(0.0, 1.0, 0.0));
+        assert!(
+            stuck
+                .view_matrix()
+                .rows
+                .iter()
+                .flatten()
+                .all(|value| value.is_finite())
+        );
+        let straight_down = Camera::look_at(
+            Vec3::new(0.0, 5.0, 0.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        let down = straight_down
+            .view_matrix()
+            .transform_point(Vec3::new(0.0, 0.0, 0.0));
+        assert!((down - Vec3::new(0.0, 0.0, -5.0)).length() < 1e-12);
+    }
+
+    #[test]
+    fn screen_center_unprojects_along_the_view() {
+        let camera = Camera::look_at(
+            Vec3::new(3.0, 4.0, 5.0),
+            Vec3::new(1.0, -1.0, 2.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        let (origin, direction) = camera.screen_to_ray(Vec2 { x: 40.0, y: 30.0 }, 80, 60);
+        let forward = (camera.target - camera.position).normalize();
+        assert!((direction - forward).length() < 1e-9);
+        // it starts on the near plane
+        assert!(((origin - camera.position).dot(forward) - camera.near).abs() < 1e-9);
+
+        // the top-left corner is up and to the left of the view
+        let (_, corner) = camera.screen_to_ray(Vec2 { x: 0.0, y: 0.0 }, 80, 60);
+        let view = camera.view_matrix();
+        let corner =
+            view.transform_point(camera.position + corner) - view.transform_point(camera.position);
+        assert!(corner.x() < 0.0 && corner.y() > 0.0 && corner.z() < 0.0);
+    }
+}
@@ -0,0 +1,620 @@
+//! exporting the rendered output to image files
+
+use crate::{HashableVec3, Pixel, Scene, Vec3, rgb8_bytes};
+use error_stack::{Report, ResultExt};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+/// An error while exporting a Scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportError;
+
+impl std::fmt::Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("failed to export scene")
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+/// An error from decoding run-length encoded pixels that are cut off or don't fit the size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RleError;
+
+impl std::fmt::Display for RleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("invalid run-length encoded pixels")
+    }
+}
+
+impl std::error::Error for RleError {}
+
+/// The order of the color channels in exported images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChannelOrder {
+    /// Red, green, blue.
+    #[default]
+    Rgb,
+    /// Blue, green, red.
+    Bgr,
+}
+
+impl ChannelOrder {
+    /// Put RGB channels in this order.
+    pub fn apply<T>(self, [r, g, b]: [T; 3]) -> [T; 3] {
+        match self {
+            Self::Rgb => [r, g, b],
+            Self::Bgr => [b, g, r],
+        }
+    }
+}
+
+impl<P: Pixel> Scene<P> {
+    /// Save the rendered output as a binary PPM (P6) image.
+    pub fn save_ppm(&self, path: impl AsRef<Path>) -> Result<(), Report<ExportError>> {
+        let path = path.as_ref();
+        let mut file = std::io::BufWriter::new(
+            std::fs::File::create(path)
+                .change_context(ExportError)
+                .attach_printable_lazy(|| format!("path: {}", path.display()))?,
+        );
+
+        write!(file, "P6\n{} {}\n255\n", self.width, self.height).change_context(ExportError)?;
+        for pixel in &self.output {
+            file.write_all(&pixel.to_vec3().to_rgb8())
+                .change_context(ExportError)?;
+        }
+        file.flush().change_context(ExportError)
+    }
+    /// Convert the triangles to an SVG image the size of the output, with one `<polygon>` per
+    /// triangle. Triangles with different colors at each point are filled with their average
+    /// color.
+    pub fn to_svg(&self) -> String {
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+            self.width, self.height
+        );
+        for triangle in self
+            .triangles
+            .iter()
+            .map(|&triangle| self.to_screen(triangle))
+        {
+            let [r, g, b] = triangle.average_color().to_rgb8();
+            let points = triangle
+                .points
+                .iter()
+                .map(|point| format!("{},{}", point.x, point.y))
+                .collect::<Vec<String>>()
+                .join(" ");
+            svg += &format!(
+                "  <polygon points=\"{points}\" fill=\"rgb({r},{g},{b})\" fill-opacity=\"{}\"/>\n",
+                triangle.alpha
+            );
+        }
+        svg += "</svg>\n";
+        svg
+    }
+    /// Save the triangles as an SVG image (see [`Scene::to_svg`]).
+    pub fn save_svg(&self, path: impl AsRef<Path>) -> Result<(), Report<ExportError>> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_svg())
+            .change_context(ExportError)
+            .attach_printable_lazy(|| format!("path: {}", path.display()))
+    }
+    /// Convert the triangles to a Wavefront OBJ model, with identical vertices shared between
+    /// faces. 3D triangles are written as they are and 2D triangles at Z = 0. Colors aren't
+    /// included.
+    pub fn to_obj(&self) -> String {
+        let triangles = self
+            .triangles_3d
+            .iter()
+            .map(|triangle| triangle.points)
+            .chain(self.triangles.iter().map(|triangle| {
+                triangle
+                    .points
+                    .map(|point| Vec3::new(point.x, point.y, 0.0))
+            }));
+
+        let mut vertices = String::new();
+        let mut faces = String::new();
+        let mut indices = HashMap::<HashableVec3, usize>::new();
+        for points in triangles {
+            let [a, b, c] = points.map(|point| {
+                let next = indices.len() + 1;
+                *indices.entry(point.into()).or_insert_with(|| {
+                    vertices += &format!("v {} {} {}\n", point.x(), point.y(), point.z());
+                    next
+                })
+            });
+            faces += &format!("f {a} {b} {c}\n");
+        }
+        vertices + &faces
+    }
+    /// Save the triangles as a Wavefront OBJ model (see [`Scene::to_obj`]).
+    pub fn save_obj(&self, path: impl AsRef<Path>) -> Result<(), Report<ExportError>> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_obj())
+            .change_context(ExportError)
+            .attach_printable_lazy(|| format!("path: {}", path.display()))
+    }
+    /// Render `frames` frames with the camera orbiting 360° around the center of the 3D
+    /// geometry (see [`Scene::bounds_3d`]), keeping its height and distance, and save them to
+    /// `out_dir` as `frame-0000.png` and so on (or `.ppm` without the `image_types` feature).
+    /// The camera is put back afterwards.
+    pub fn render_turntable(
+        &mut self,
+        frames: usize,
+        out_dir: &Path,
+    ) -> Result<(), Report<ExportError>> {
+        std::fs::create_dir_all(out_dir)
+            .change_context(ExportError)
+            .attach_printable_lazy(|| format!("path: {}", out_dir.display()))?;
+
+        let original = self.camera;
+        let center = match self.bounds_3d() {
+            Some((min, max)) => (min + max) * 0.5,
+            None => original.target,
+        };
+        let offset = original.position - center;
+
+        let mut result = Ok(());
+        for frame in 0..frames {
+            let angle = std::f64::consts::TAU * frame as f64 / frames as f64;
+            let (sin, cos) = angle.sin_cos();
+            self.camera.position = center
+                + Vec3::new(
+                    offset.x() * cos + offset.z() * sin,
+                    offset.y(),
+                    offset.z() * cos - offset.x() * sin,
+                );
+            self.camera.target = center;
+            self.clear_color_buffer();
+            self.clear_depth_buffer();
+            self.render();
+
+            #[cfg(feature = "image_types")]
+            let saved = self.save_png(out_dir.join(format!("frame-{frame:04}.png")));
+            #[cfg(not(feature = "image_types"))]
+            let saved = self.save_ppm(out_dir.join(format!("frame-{frame:04}.ppm")));
+            if let Err(err) = saved {
+                result = Err(err.attach_printable(format!("frame: {frame}")));
+                break;
+            }
+        }
+        self.camera = original;
+        result
+    }
+    /// Convert the rendered output to 8-bit RGBA bytes (or BGRA, depending on
+    /// [`Scene::channel_order`]), row by row, with opaque alpha.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let colors = self
+            .output
+            .iter()
+            .map(|pixel| pixel.to_vec3())
+            .collect::<Vec<Vec3>>();
+        rgb8_bytes(&colors)
+            .chunks_exact(3)
+            .flat_map(|rgb| {
+                let [a, b, c] = self.channel_order.apply([rgb[0], rgb[1], rgb[2]]);
+                [a, b, c, 255]
+            })
+            .collect()
+    }
+    /// Convert the rendered output to an egui image, e.g. for showing it in a texture with
+    /// `ctx.load_texture`. Colors are clamped to 0.0 to 1.0 (see [`Vec3::to_rgb8`]), and egui
+    /// always takes RGB, so [`Scene::channel_order`] is ignored.
+    #[cfg(feature = "egui")]
+    pub fn to_egui_color_image(&self) -> egui::ColorImage {
+        let colors = self
+            .output
+            .iter()
+            .map(|pixel| pixel.to_vec3())
+            .collect::<Vec<Vec3>>();
+        egui::ColorImage::from_rgb(self.size(), &rgb8_bytes(&colors))
+    }
+    /// Compress the rendered output, quantized to 8-bit RGB (see [`Vec3::to_rgb8`]), with
+    /// run-length encoding: each run of up to 255 identical pixels, row by row, is stored as
+    /// 4 bytes, the length followed by the red, green and blue. Decode it with
+    /// [`Scene::decode_rle`]. This works best on outputs with large flat areas; the worst case
+    /// is a third bigger than raw 8-bit RGB.
+    pub fn encode_rle(&self) -> Vec<u8> {
+        let colors = self
+            .output
+            .iter()
+            .map(|pixel| pixel.to_vec3())
+            .collect::<Vec<Vec3>>();
+        let mut bytes = Vec::new();
+        for rgb in rgb8_bytes(&colors).chunks_exact(3) {
+            match bytes.rchunks_exact_mut(4).next() {
+                Some([length, r, g, b]) if *length < u8::MAX && [*r, *g, *b] == rgb => {
+                    (*length) += 1;
+                }
+                _ => bytes.extend([1, rgb[0], rgb[1], rgb[2]]),
+            }
+        }
+        bytes
+    }
+    /// Convert the rendered output to an image, with the channels in [`Scene::channel_order`].
+    #[cfg(feature = "image_types")]
+    pub fn to_rgb_image(&self) -> image::RgbImage {
+        image::RgbImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            image::Rgb(
+                self.channel_order
+                    .apply(self.pixel(x as usize, y as usize).to_rgb8()),
+            )
+        })
+    }
+    /// Save the rendered output as a PNG image, with the channels in [`Scene::channel_order`].
+    #[cfg(feature = "image_types")]
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), Report<ExportError>> {
+        let path = path.as_ref();
+        self.to_rgb_image()
+            .save_with_format(path, image::ImageFormat::Png)
+            .change_context(ExportError)
+            .attach_printable_lazy(|| format!("path: {}", path.display()))
+    }
+    /// Save the rendered output as a 16-bit PNG image, with the channels in
+    /// [`Scene::channel_order`]. Colors are clamped to 0.0 to 1.0 (see [`Vec3::to_rgb16`]).
+    #[cfg(feature = "image_types")]
+    pub fn save_png16(&self, path: impl AsRef<Path>) -> Result<(), Report<ExportError>> {
+        let path = path.as_ref();
+        image::ImageBuffer::<image::Rgb<u16>, _>::from_fn(
+            self.width as u32,
+            self.height as u32,
+            |x, y| {
+                image::Rgb(
+                    self.channel_order
+                        .apply(self.pixel(x as usize, y as usize).to_rgb16()),
+                )
+            },
+        )
+        .save_with_format(path, image::ImageFormat::Png)
+        .change_context(ExportError)
+        .attach_printable_lazy(|| format!("path: {}", path.display()))
+    }
+    /// Save the depth buffer as a 16-bit grayscale PNG, with depth `near` black and `far` white.
+    /// Depths beyond them are clamped, so pixels nothing was drawn on (at the farthest depth)
+    /// come out white, as long as `far` is on the same side as it (e.g. `far` is less than
+    /// `near` with [`Scene::set_reversed_z`]).
+    #[cfg(feature = "image_types")]
+    pub fn save_depth_png(
+        &self,
+        path: impl AsRef<Path>,
+        near: f64,
+        far: f64,
+    ) -> Result<(), Report<ExportError>> {
+        let path = path.as_ref();
+        if !near.is_finite() || !far.is_finite() || near == far {
+            return Err(Report::new(ExportError).attach_printable(format!(
+                "the depth range {near} to {far} needs to be finite and not empty"
+            )));
+        }
+        image::ImageBuffer::<image::Luma<u16>, _>::from_fn(
+            self.width as u32,
+            self.height as u32,
+            |x, y| {
+                let depth = self.depth(x as usize, y as usize);
+                let normalized = ((depth - near) / (far - near)).clamp(0.0, 1.0);
+                image::Luma([(normalized * u16::MAX as f64).round() as u16])
+            },
+        )
+        .save_with_format(path, image::ImageFormat::Png)
+        .change_context(ExportError)
+        .attach_printable_lazy(|| format!("path: {}", path.display()))
+    }
+}
+
+impl Scene {
+    /// Decode pixels encoded by [`Scene::encode_rle`] from an output `width` by `height`, row
+    /// by row, with each channel from 0.0 to 1.0.
+    pub fn decode_rle(
+        bytes: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Result<Vec<Vec3>, Report<RleError>> {
+        if !bytes.len().is_multiple_of(4) {
+            return Err(Report::new(RleError).attach_printable(format!(
+                "{} bytes isn't a whole number of runs",
+                bytes.len()
+            )));
+        }
+        let mut pixels = Vec::with_capacity(width * height);
+        for run in bytes.chunks_exact(4) {
+            let (length, rgb) = (run[0] as usize, [run[1], run[2], run[3]]);
+            if length == 0 || pixels.len() + length > width * height {
+                return Err(Report::new(RleError).attach_printable(format!(
+                    "run of {length} after {} pixels, for {width}x{height}",
+                    pixels.len()
+                )));
+            }
+            pixels.extend(std::iter::repeat_n(<[u8; 3]>::to_vec3(rgb), length));
+        }
+        if pixels.len() != width * height {
+            return Err(Report::new(RleError)
+                .attach_printable(format!("{} pixels, not {width}x{height}", pixels.len())));
+        }
+        Ok(pixels)
+    }
+}
+
+#[cfg(all(feature = "rayon", feature = "image_types"))]
+impl Scene {
+    /// Save rendered outputs (e.g. copies of [`Scene::pixels`]), each `width` by `height`, to
+    /// `out_dir` as `frame-0000.png` and so on, encoding them on rayon's thread pool. This
+    /// returns straight away so the caller can keep rendering; join the handle to wait for the
+    /// files and find out if saving any of them failed.
+    pub fn save_frames_parallel(
+        frames: Vec<Vec<Vec3>>,
+        (width, height): (usize, usize),
+        out_dir: &Path,
+    ) -> std::thread::JoinHandle<Result<(), Report<ExportError>>> {
+        use rayon::prelude::*;
+
+        let out_dir = out_dir.to_path_buf();
+        std::thread::spawn(move || {
+            std::fs::create_dir_all(&out_dir)
+                .change_context(ExportError)
+                .attach_printable_lazy(|| format!("path: {}", out_dir.display()))?;
+
+            frames
+                .into_par_iter()
+                .enumerate()
+                .try_for_each(|(frame, pixels)| {
+                    if pixels.len() != width * height {
+                        return Err(Report::new(ExportError).attach_printable(format!(
+                            "frame {frame} has {} pixels, not {width}x{height}",
+                            pixels.len()
+                        )));
+                    }
+                    let path = out_dir.join(format!("frame-{frame:04}.png"));
+                    image::RgbImage::from_fn(width as u32, height as u32, |x, y| {
+                        image::Rgb(pixels[y as usize * width + x as usize].to_rgb8())
+                    })
+                    .save_with_format(&path, image::ImageFormat::Png)
+                    .change_context(ExportError)
+                    .attach_printable_lazy(|| format!("path: {}", path.display()))
+                })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Tri2, Vec2};
+
+    #[test]
+    fn svg_has_a_polygon_per_triangle() {
+        let mut scene = Scene::<Vec3>::sized(40, 30);
+        for i in 0..3 {
+            let offset = i as f64 * 10.0;
+            scene.add_triangle(Tri2::flat(
+                [
+                    Vec2 { x: offset, y: 0.0 },
+                    Vec2 {
+                        x: offset + 10.0,
+                        y: 0.0,
+                    },
+                    Vec2 { x: offset, y: 10.0 },
+                ],
+                Vec3::new(1.0, 0.0, 0.0),
+            ));
+        }
+        let svg = scene.to_svg();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("width=\"40\" height=\"30\""));
+        assert_eq!(svg.matches("<polygon").count(), 3);
+    }
+
+    #[test]
+    fn obj_round_trips_the_triangle_count() {
+        let mut scene = Scene::<Vec3>::sized(20, 20);
+        for triangle in crate::Mesh::cube(2.0).triangles() {
+            scene.add_triangle_3d(triangle);
+        }
+        scene.add_triangle(Tri2::flat(
+            [
+                Vec2 { x: 0.0, y: 0.0 },
+                Vec2 { x: 5.0, y: 0.0 },
+                Vec2 { x: 0.0, y: 5.0 },
+            ],
+            Vec3::new(1.0, 1.0, 1.0),
+        ));
+        let path = std::env::temp_dir().join(format!("threed-test-{}.obj", std::process::id()));
+        scene.save_obj(&path).unwrap();
+        let obj = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let vertices = obj
+            .lines()
+            .filter_map(|line| line.strip_prefix("v "))
+            .map(|line| {
+                line.split_whitespace()
+                    .map(|value| value.parse::<f64>().unwrap())
+                    .collect::<Vec<f64>>()
+            })
+            .collect::<Vec<Vec<f64>>>();
+        let faces = obj
+            .lines()
+            .filter_map(|line| line.strip_prefix("f "))
+            .map(|line| {
+                line.split_whitespace()
+                    .map(|index| index.parse::<usize>().unwrap())
+                    .collect::<Vec<usize>>()
+            })
+            .collect::<Vec<Vec<usize>>>();
+        assert_eq!(faces.len(), 13);
+        // the cube's 8 corners are shared between its faces, plus the 2D triangle's 3 points
+        assert_eq!(vertices.len(), 8 + 3);
+        assert!(
+            faces
+                .iter()
+                .flatten()
+                .all(|&index| (1..=vertices.len()).contains(&index))
+        );
+    }
+
+    #[test]
+    fn turntable_writes_a_file_per_frame() {
+        let mut scene = Scene::<Vec3>::sized(16, 16);
+        scene.add_mesh(crate::Mesh::cube(2.0));
+        let camera = scene.camera;
+        let dir = std::env::temp_dir().join(format!("threed-turntable-{}", std::process::id()));
+        scene.render_turntable(3, &dir).unwrap();
+        let files = std::fs::read_dir(&dir).unwrap().count();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(files, 3);
+        assert_eq!(scene.camera, camera);
+    }
+
+    #[test]
+    fn bgr_swaps_red_and_blue() {
+        let mut scene = Scene::<Vec3>::sized(1, 1);
+        scene.clear_color = Vec3::new(1.0, 0.0, 0.0);
+        scene.clear_color_buffer();
+        assert_eq!(scene.to_rgba8(), [255, 0, 0, 255]);
+        scene.channel_order = ChannelOrder::Bgr;
+        assert_eq!(scene.to_rgba8(), [0, 0, 255, 255]);
+        #[cfg(feature = "image_types")]
+        assert_eq!(scene.to_rgb_image().get_pixel(0, 0).0, [0, 0, 255]);
+    }
+
+    #[test]
+    #[cfg(feature = "image_types")]
+    fn png16_maps_one_to_65535() {
+        let mut scene = Scene::<Vec3>::sized(2, 1);
+        scene.clear_color = Vec3::new(1.0, 0.0, 2.0);
+        scene.clear_color_buffer();
+        let path = std::env::temp_dir().join(format!("threed-test-{}.png", std::process::id()));
+        scene.save_png16(&path).unwrap();
+        let image = image::open(&path).unwrap().into_rgb16();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(image.dimensions(), (2, 1));
+        assert_eq!(image.get_pixel(1, 0).0, [65535, 0, 65535]);
+        assert_eq!(Vec3::new(0.5, 0.0, -1.0).to_rgb16(), [32768, 0, 0]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "rayon", feature = "image_types"))]
+    fn parallel_frames_are_saved_and_decode() {
+        let frames = (0..4)
+            .map(|frame| vec![Vec3::new(frame as f64 / 4.0, 0.0, 1.0); 3 * 2])
+            .collect::<Vec<Vec<Vec3>>>();
+        let dir = std::env::temp_dir().join(format!("threed-frames-{}", std::process::id()));
+        Scene::save_frames_parallel(frames, (3, 2), &dir)
+            .join()
+            .unwrap()
+            .unwrap();
+        let mut names = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<String>>();
+        names.sort();
+        let third = image::open(dir.join("frame-0002.png")).unwrap().into_rgb8();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            names,
+            [
+                "frame-0000.png",
+                "frame-0001.png",
+                "frame-0002.png",
+                "frame-0003.png"
+            ]
+        );
+        assert_eq!(third.dimensions(), (3, 2));
+        assert_eq!(third.get_pixel(2, 1).0, [128, 0, 255]);
+
+        // frames of the wrong size are an error
+        let result =
+            Scene::save_frames_parallel(vec![vec![Vec3::new(0.0, 0.0, 0.0); 5]], (3, 2), &dir)
+                .join()
+                .unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "egui")]
+    fn egui_image_has_the_size_and_colors() {
+        let mut scene = Scene::<Vec3>::sized(5, 3);
+        scene.clear_color = Vec3::new(0.0, 0.0, 1.0);
+        scene.clear_color_buffer();
+        scene.add_triangle(Tri2::flat(
+            [
+                Vec2 { x: 0.0, y: 0.0 },
+                Vec2 { x: 3.0, y: 0.0 },
+                Vec2 { x: 0.0, y: 3.0 },
+            ],
+            Vec3::new(2.0, 0.5, -1.0),
+        ));
+        scene.render();
+        scene.channel_order = ChannelOrder::Bgr;
+        let image = scene.to_egui_color_image();
+        assert_eq!(image.size, [5, 3]);
+        assert_eq!(image.pixels.len(), 15);
+        assert_eq!(image[(0, 0)], egui::Color32::from_rgb(255, 128, 0));
+        assert_eq!(image[(4, 2)], egui::Color32::from_rgb(0, 0, 255));
+    }
+
+    #[test]
+    fn rle_round_trips_a_mostly_solid_output() {
+        let mut scene = Scene::<Vec3>::sized(64, 48);
+        scene.clear_color = Vec3::new(0.2, 0.4, 0.6);
+        scene.clear_color_buffer();
+        scene.add_triangle(Tri2::flat(
+            [
+                Vec2 { x: 10.0, y: 10.0 },
+                Vec2 { x: 30.0, y: 12.0 },
+                Vec2 { x: 15.0, y: 40.0 },
+            ],
+            Vec3::new(1.0, 0.5, 0.0),
+        ));
+        scene.render();
+
+        let bytes = scene.encode_rle();
+        assert!(bytes.len() < 64 * 48 * 3);
+        let decoded = Scene::decode_rle(&bytes, 64, 48).unwrap();
+        let quantized = |colors: &[Vec3]| {
+            colors
+                .iter()
+                .map(|color| color.to_rgb8())
+                .collect::<Vec<[u8; 3]>>()
+        };
+        assert_eq!(quantized(&decoded), quantized(scene.pixels()));
+
+        assert!(Scene::decode_rle(&bytes, 64, 47).is_err());
+        assert!(Scene::decode_rle(&bytes[1..], 64, 48).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "image_types")]
+    fn depth_png_is_white_where_nothing_was_drawn() {
+        let mut scene = Scene::<Vec3>::sized(20, 20);
+        let path = std::env::temp_dir().join(format!("threed-depth-{}.png", std::process::id()));
+        scene.save_depth_png(&path, 0.0, 1.0).unwrap();
+        let empty = image::open(&path).unwrap().into_luma16();
+        assert_eq!(empty.dimensions(), (20, 20));
+        assert!(empty.pixels().all(|pixel| pixel.0 == [u16::MAX]));
+
+        scene.add_triangle_3d(crate::Tri3 {
+            points: [
+                Vec3::new(-1.0, -1.0, 0.0),
+                Vec3::new(1.0, -1.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            ],
+            color: Vec3::new(1.0, 1.0, 1.0),
+            material: 0,
+        });
+        scene.render();
+        scene.save_depth_png(&path, 0.0, 1.0).unwrap();
+        let drawn = image::open(&path).unwrap().into_luma16();
+        std::fs::remove_file(&path).unwrap();
+        let [middle] = drawn.get_pixel(10, 10).0;
+        assert!(middle > 0 && middle < u16::MAX);
+        assert_eq!(drawn.get_pixel(0, 0).0, [u16::MAX]);
+
+        assert!(scene.save_depth_png(&path, 1.0, 1.0).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+//! 2D geometry on sets of points and polygons
+
+use crate::{DEGENERATE_AREA, Tri2, Vec2, Vec3};
+
+/// Clip a convex polygon to the rectangle from `min` to `max`, with the Sutherland-Hodgman
+/// algorithm. The corners stay in the same order, and an empty polygon is returned if none of it
+/// is inside.
+pub(crate) fn clip_polygon(points: &[Vec2], min: Vec2, max: Vec2) -> Vec<Vec2> {
+    // each side of the rectangle as (how far inside a point is, i.e. positive if it's inside)
+    let sides: [&dyn Fn(Vec2) -> f64; 4] = [
+        &|p| p.x - min.x,
+        &|p| max.x - p.x,
+        &|p| p.y - min.y,
+        &|p| max.y - p.y,
+    ];
+    let mut polygon = points.to_vec();
+    for inside in sides {
+        let input = std::mem::take(&mut polygon);
+        for (i, &point) in input.iter().enumerate() {
+            let previous = input[(i + input.len() - 1) % input.len()];
+            let (distance, previous_distance) = (inside(point), inside(previous));
+            if (distance >= 0.0) != (previous_distance >= 0.0) {
+                let t = previous_distance / (previous_distance - distance);
+                polygon.push(previous + (point - previous) * t);
+            }
+            if distance >= 0.0 {
+                polygon.push(point);
+            }
+        }
+    }
+    polygon
+}
+
+/// The convex hull of `points`, with Andrew's monotone chain algorithm. The corners are returned
+/// counter-clockwise with Y pointing up (so clockwise on the screen, where Y points down),
+/// starting from the leftmost one. Points along the edges of the hull and duplicates are left
+/// out, so collinear points give just the two ends and fewer than 3 distinct points are returned
+/// as they are (sorted). Non-finite points are ignored.
+pub fn convex_hull(points: &[Vec2]) -> Vec<Vec2> {
+    /// Is `c` to the left of the line from `a` to `b` (with Y pointing up)?
+    fn left_turn(a: Vec2, b: Vec2, c: Vec2) -> bool {
+        let (ab, ac) = (b - a, c - a);
+        ab.x * ac.y - ab.y * ac.x > 0.0
+    }
+
+    let mut points = points
+        .iter()
+        .copied()
+        .filter(|point| point.is_finite())
+        .collect::<Vec<Vec2>>();
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    let mut hull: Vec<Vec2> = Vec::with_capacity(points.len() + 1);
+    // the lower half going right, then the upper half going back left
+    for &point in &points {
+        while hull.len() >= 2 && !left_turn(hull[hull.len() - 2], hull[hull.len() - 1], point) {
+            hull.pop();
+        }
+        hull.push(point);
+    }
+    let lower_len = hull.len();
+    for &point in points.iter().rev().skip(1) {
+        while hull.len() > lower_len
+            && !left_turn(hull[hull.len() - 2], hull[hull.len() - 1], point)
+        {
+            hull.pop();
+        }
+        hull.push(point);
+    }
+    // the last point is the first one again
+    hull.pop();
+    hull
+}
+
+/// The Delaunay triangulation of `points`, with the Bowyer-Watson algorithm: triangles joining
+/// them so no point is inside another triangle's circumcircle, which avoids thin slivers where
+/// it can. The triangles are white, opaque, and clockwise on the screen. Duplicates and
+/// non-finite points are ignored, and collinear points give no triangles (since they'd all be
+/// flat). Points on a shared circle (like the corners of a square) can be split either way.
+pub fn delaunay(points: &[Vec2]) -> Vec<Tri2> {
+    /// The center and squared radius of the circle through the corners of a triangle. A flat
+    /// triangle's circle is infinite, so it contains every point.
+    fn circumcircle([a, b, c]: [Vec2; 3]) -> (Vec2, f64) {
+        // relative to `a`, to keep the precision
+        let (b, c) = (b - a, c - a);
+        let d = 2.0 * (b.x * c.y - b.y * c.x);
+        if d.abs() < DEGENERATE_AREA {
+            return (a, f64::INFINITY);
+        }
+        let (b_sq, c_sq) = (b.dot(b), c.dot(c));
+        let center = Vec2 {
+            x: (c.y * b_sq - b.y * c_sq) / d,
+            y: (b.x * c_sq - c.x * b_sq) / d,
+        };
+        (a + center, center.dot(center))
+    }
+
+    let mut vertices = points
+        .iter()
+        .copied()
+        .filter(|point| point.is_finite())
+        .collect::<Vec<Vec2>>();
+    vertices.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    vertices.dedup();
+    if vertices.len() < 3 {
+        return Vec::new();
+    }
+    let count = vertices.len();
+
+    // a triangle far around all of them to start from, removed at the end
+    let (min, max) = vertices.iter().fold((vertices[0], vertices[0]), |(min, max), point| {
+        (
+            Vec2 {
+                x: min.x.min(point.x),
+                y: min.y.min(point.y),
+            },
+            Vec2 {
+                x: max.x.max(point.x),
+                y: max.y.max(point.y),
+            },
+        )
+    });
+    let middle = (min + max) * 0.5;
+    let size = (max.x - min.x).max(max.y - min.y);
+    vertices.extend([
+        Vec2 {
+            x: middle.x - size * 1000.0,
+            y: middle.y - size,
+        },
+        Vec2 {
+            x: middle.x,
+            y: middle.y + size * 1000.0,
+        },
+        Vec2 {
+            x: middle.x + size * 1000.0,
+            y: middle.y - size,
+        },
+    ]);
+
+    let corners = |triangle: [usize; 3]| triangle.map(|i| vertices[i]);
+    // (vertex indices, circumcircle)
+    let start = [count, count + 1, count + 2];
+    let mut triangles = vec![(start, circumcircle(corners(start)))];
+    for (i, &point) in vertices[..count].iter().enumerate() {
+        // the triangles whose circumcircles contain the point leave a hole around it...
+        let mut edges = Vec::new();
+        triangles.retain(|&(triangle, (center, radius_sq))| {
+            let offset = point - center;
+            if offset.dot(offset) >= radius_sq {
+                return true;
+            }
+            edges.extend([0, 1, 2].map(|j| (triangle[j], triangle[(j + 1) % 3])));
+            false
+        });
+        // ...which is filled with triangles from its edges to the point
+        for &(from, to) in &edges {
+            let shared = edges
+                .iter()
+                .filter(|&&(a, b)| (a, b) == (from, to) || (a, b) == (to, from))
+                .count()
+                > 1;
+            if !shared {
+                let triangle = [from, to, i];
+                triangles.push((triangle, circumcircle(corners(triangle))));
+            }
+        }
+    }
+
+    triangles
+        .into_iter()
+        .filter(|(triangle, _)| triangle.iter().all(|&i| i < count))
+        .filter_map(|(triangle, _)| {
+            let mut points = corners(triangle);
+            let tri = Tri2 {
+                points,
+                colors: [Vec3::new(1.0, 1.0, 1.0); 3],
+                alpha: 1.0,
+            };
+            if tri.is_degenerate() {
+                return None;
+            }
+            if tri.signed_area() < 0.0 {
+                points.swap(1, 2);
+            }
+            Some(Tri2 { points, ..tri })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convex_hull_of_a_square_and_an_inside_point() {
+        let p = |x, y| Vec2 { x, y };
+        let hull = convex_hull(&[
+            p(1.0, 1.0),
+            p(0.0, 2.0),
+            p(2.0, 0.0),
+            p(0.5, 1.5),
+            p(0.0, 0.0),
+            p(2.0, 2.0),
+            p(1.0, 0.0),
+        ]);
+        assert_eq!(hull, [p(0.0, 0.0), p(2.0, 0.0), p(2.0, 2.0), p(0.0, 2.0)]);
+
+        let collinear = [p(2.0, 2.0), p(0.0, 0.0), p(1.0, 1.0)];
+        assert_eq!(convex_hull(&collinear), [p(0.0, 0.0), p(2.0, 2.0)]);
+        assert_eq!(
+            convex_hull(&[p(1.0, 0.0), p(0.0, 0.0)]),
+            [p(0.0, 0.0), p(1.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn delaunay_splits_quads_along_a_diagonal() {
+        let point = |x, y| Vec2 { x, y };
+        let shares = |triangles: &[Tri2], a: Vec2, b: Vec2| {
+            triangles
+                .iter()
+                .all(|triangle| triangle.points.contains(&a) && triangle.points.contains(&b))
+        };
+
+        let square = [
+            point(0.0, 0.0),
+            point(10.0, 0.0),
+            point(10.0, 10.0),
+            point(0.0, 10.0),
+        ];
+        let triangles = delaunay(&square);
+        assert_eq!(triangles.len(), 2);
+        // the corners are on one circle, so either diagonal will do
+        assert!(
+            shares(&triangles, square[0], square[2]) || shares(&triangles, square[1], square[3])
+        );
+        let area = triangles
+            .iter()
+            .map(|triangle| triangle.signed_area().abs())
+            .sum::<f64>();
+        assert!((area - 100.0).abs() < 1e-9);
+
+        // a flat diamond is split along its short diagonal, with a duplicate ignored
+        let diamond = [
+            point(0.0, 0.0),
+            point(4.0, -1.0),
+            point(8.0, 0.0),
+            point(4.0, 1.0),
+            point(8.0, 0.0),
+        ];
+        let triangles = delaunay(&diamond);
+        assert_eq!(triangles.len(), 2);
+        assert!(shares(&triangles, diamond[1], diamond[3]));
+
+        let line = [point(0.0, 0.0), point(1.0, 1.0), point(2.0, 2.0)];
+        assert!(delaunay(&line).is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+//! loading glTF scenes
+
+use crate::{Mat4, Scene, Tri3, Vec3};
+use error_stack::{Report, ResultExt};
+use std::path::Path;
+use tracing::{Level, event};
+
+/// An error while loading a glTF scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GltfError;
+
+impl std::fmt::Display for GltfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("failed to load glTF scene")
+    }
+}
+
+impl std::error::Error for GltfError {}
+
+impl Scene {
+    /// Load the meshes of a glTF file as 3D triangles, with node transforms applied and colored
+    /// by the base color of their material. Textures are ignored.
+    pub fn load_gltf(path: impl AsRef<Path>) -> Result<Scene, Report<GltfError>> {
+        let path = path.as_ref();
+        let (document, buffers, _) = ::gltf::import(path)
+            .change_context(GltfError)
+            .attach_printable_lazy(|| format!("path: {}", path.display()))?;
+        Self::from_gltf(&document, &buffers)
+    }
+    /// Load the meshes of a glTF file already in memory, like [`Scene::load_gltf`].
+    pub fn load_gltf_slice(bytes: &[u8]) -> Result<Scene, Report<GltfError>> {
+        let (document, buffers, _) = ::gltf::import_slice(bytes).change_context(GltfError)?;
+        Self::from_gltf(&document, &buffers)
+    }
+    /// Make a Scene from the default (or first) scene of a glTF document.
+    fn from_gltf(
+        document: &::gltf::Document,
+        buffers: &[::gltf::buffer::Data],
+    ) -> Result<Scene, Report<GltfError>> {
+        let gltf_scene = document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+            .ok_or_else(|| Report::new(GltfError).attach_printable("no scenes in document"))?;
+
+        let mut scene = Scene::empty();
+        for node in gltf_scene.nodes() {
+            add_gltf_node(&mut scene, &node, Mat4::identity(), buffers)?;
+        }
+        Ok(scene)
+    }
+}
+
+/// Add the triangles of a node and its children to the scene, where `parent` is the combined
+/// transform of the node's ancestors.
+fn add_gltf_node(
+    scene: &mut Scene,
+    node: &::gltf::Node,
+    parent: Mat4,
+    buffers: &[::gltf::buffer::Data],
+) -> Result<(), Report<GltfError>> {
+    // glTF matrices are column-major
+    let columns = node.transform().matrix();
+    let transform = parent
+        * Mat4 {
+            rows: std::array::from_fn(|i| std::array::from_fn(|j| columns[j][i] as f64)),
+        };
+
+    if let Some(mesh) = node.mesh() {
+        for primitive in mesh.primitives() {
+            if primitive.mode() != ::gltf::mesh::Mode::Triangles {
+                event!(
+                    Level::WARN,
+                    "skipping glTF primitive with mode {:?}",
+                    primitive.mode()
+                );
+                continue;
+            }
+
+            let [r, g, b, _] = primitive
+                .material()
+                .pbr_metallic_roughness()
+                .base_color_factor();
+            let color = Vec3::new(r as f64, g as f64, b as f64);
+
+            let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &**data));
+            let positions = reader
+                .read_positions()
+                .ok_or_else(|| {
+                    Report::new(GltfError).attach_printable("primitive has no positions")
+                })?
+                .map(|[x, y, z]| transform.transform_point(Vec3::new(x as f64, y as f64, z as f64)))
+                .collect::<Vec<Vec3>>();
+            let indices = match reader.read_indices() {
+                Some(indices) => indices.into_u32().map(|i| i as usize).collect(),
+                None => (0..positions.len()).collect::<Vec<usize>>(),
+            };
+
+            for triangle in indices.chunks_exact(3) {
+                let points =
+                    [triangle[0], triangle[1], triangle[2]].map(|i| positions.get(i).copied());
+                let [Some(a), Some(b), Some(c)] = points else {
+                    return Err(Report::new(GltfError).attach_printable("index out of bounds"));
+                };
+                scene.add_triangle_3d(Tri3 {
+                    points: [a, b, c],
+                    color,
+                    material: 0,
+                });
+            }
+        }
+    }
+
+    for child in node.children() {
+        add_gltf_node(scene, &child, transform, buffers)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A triangle mesh used by two nodes, the second a child moved 2 along X.
+    const TINY_GLTF: &str = r#"{
+    "asset": {"version": "2.0"},
+    "scene": 0,
+    "scenes": [{"nodes": [0]}],
+    "nodes": [
+        {"mesh": 0, "children": [1]},
+        {"mesh": 0, "translation": [2.0, 0.0, 0.0]}
+    ],
+    "meshes": [{"primitives": [{"attributes": {"POSITION": 0}, "material": 0}]}],
+    "materials": [{"pbrMetallicRoughness": {"baseColorFactor": [1.0, 0.5, 0.0, 1.0]}}],
+    "buffers": [{
+        "byteLength": 36,
+        "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA"
+    }],
+    "bufferViews": [{"buffer": 0, "byteLength": 36}],
+    "accessors": [{
+        "bufferView": 0,
+        "componentType": 5126,
+        "count": 3,
+        "type": "VEC3",
+        "min": [0.0, 0.0, 0.0],
+        "max": [1.0, 1.0, 0.0]
+    }]
+}"#;
+
+    #[test]
+    fn loads_a_tiny_gltf() {
+        let scene = Scene::load_gltf_slice(TINY_GLTF.as_bytes()).unwrap();
+        let triangles = scene.triangles_3d();
+        assert_eq!(triangles.len(), 2);
+        assert!(
+            triangles
+                .iter()
+                .all(|triangle| triangle.color == Vec3::new(1.0, 0.5, 0.0))
+        );
+        assert_eq!(triangles[0].points[1], Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(triangles[1].points[1], Vec3::new(3.0, 0.0, 0.0));
+    }
+}
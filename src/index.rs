@@ -0,0 +1,208 @@
+//! a quadtree of 2D triangles, for finding the ones at a point quickly
+
+use crate::{Tri2, Vec2};
+
+/// The most triangles a node holds before it's split.
+const MAX_ITEMS: usize = 8;
+/// The most times the root can be split down, so the smallest nodes are 1/256 of its size.
+const MAX_DEPTH: usize = 8;
+
+/// A bounding box: top-left, and bottom-right.
+type Bounds = (Vec2, Vec2);
+
+/// Whether `p` is inside `bounds`, including on the edges.
+fn inside((top_left, bottom_right): Bounds, p: Vec2) -> bool {
+    p.x >= top_left.x && p.x <= bottom_right.x && p.y >= top_left.y && p.y <= bottom_right.y
+}
+
+/// Whether `inner` is entirely inside `outer`.
+fn fits(inner: Bounds, outer: Bounds) -> bool {
+    inside(outer, inner.0) && inside(outer, inner.1)
+}
+
+/// A rectangle of the quadtree.
+#[derive(Debug, Clone, PartialEq)]
+struct Node {
+    /// The area the node covers.
+    bounds: Bounds,
+    /// The triangles (indices and bounding boxes) that are in this node but don't fit in just
+    /// one of its children.
+    items: Vec<(usize, Bounds)>,
+    /// The index of the first of the 4 children, which are next to each other, if it's split.
+    children: Option<usize>,
+}
+
+/// A quadtree of the bounding boxes of 2D triangles, for finding the ones that might cover a
+/// point without checking all of them. [`Scene::hit_test`](crate::Scene::hit_test) keeps one
+/// for the Scene's triangles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneIndex {
+    /// The nodes, with the root first. Empty if there are no triangles.
+    nodes: Vec<Node>,
+}
+
+impl SceneIndex {
+    /// Build an index of `triangles`. Non-finite triangles are left out, since they can't cover
+    /// anything.
+    pub fn new(triangles: &[Tri2]) -> Self {
+        let items = triangles
+            .iter()
+            .enumerate()
+            .filter(|(_, triangle)| triangle.is_finite())
+            .map(|(i, triangle)| (i, triangle.bounding_box()))
+            .collect::<Vec<(usize, Bounds)>>();
+        let Some(bounds) = items.iter().map(|&(_, bounds)| bounds).reduce(|a, b| {
+            (
+                Vec2 {
+                    x: a.0.x.min(b.0.x),
+                    y: a.0.y.min(b.0.y),
+                },
+                Vec2 {
+                    x: a.1.x.max(b.1.x),
+                    y: a.1.y.max(b.1.y),
+                },
+            )
+        }) else {
+            return Self { nodes: Vec::new() };
+        };
+
+        let mut nodes = vec![Node {
+            bounds,
+            items,
+            children: None,
+        }];
+        let mut to_split = vec![(0, 0)];
+        while let Some((index, depth)) = to_split.pop() {
+            if nodes[index].items.len() <= MAX_ITEMS || depth >= MAX_DEPTH {
+                continue;
+            }
+            let (top_left, bottom_right) = nodes[index].bounds;
+            let middle = (top_left + bottom_right) * 0.5;
+            let quadrants = [
+                (top_left, middle),
+                (
+                    Vec2 {
+                        x: middle.x,
+                        y: top_left.y,
+                    },
+                    Vec2 {
+                        x: bottom_right.x,
+                        y: middle.y,
+                    },
+                ),
+                (
+                    Vec2 {
+                        x: top_left.x,
+                        y: middle.y,
+                    },
+                    Vec2 {
+                        x: middle.x,
+                        y: bottom_right.y,
+                    },
+                ),
+                (middle, bottom_right),
+            ];
+
+            let mut children = quadrants.map(|bounds| Node {
+                bounds,
+                items: Vec::new(),
+                children: None,
+            });
+            let mut stay = Vec::new();
+            for item in std::mem::take(&mut nodes[index].items) {
+                match children.iter_mut().find(|child| fits(item.1, child.bounds)) {
+                    Some(child) => child.items.push(item),
+                    None => stay.push(item),
+                }
+            }
+            // splitting is pointless if everything is too big for the children
+            if children.iter().all(|child| child.items.is_empty()) {
+                nodes[index].items = stay;
+                continue;
+            }
+            nodes[index].items = stay;
+            let first = nodes.len();
+            nodes[index].children = Some(first);
+            nodes.extend(children);
+            to_split.extend((first..first + 4).map(|child| (child, depth + 1)));
+        }
+        Self { nodes }
+    }
+    /// The indices of the triangles whose bounding boxes contain `p`, including on their edges,
+    /// in no particular order. This includes every triangle covering `p`, but it can include
+    /// some that don't, so check them with [`Tri2::contains`].
+    pub fn query(&self, p: Vec2) -> impl Iterator<Item = usize> + '_ {
+        let mut to_visit = match self.nodes.first() {
+            Some(root) if inside(root.bounds, p) => vec![0],
+            _ => Vec::new(),
+        };
+        let mut items: &[(usize, Bounds)] = &[];
+        std::iter::from_fn(move || {
+            loop {
+                if let Some((&(index, bounds), rest)) = items.split_first() {
+                    items = rest;
+                    if inside(bounds, p) {
+                        return Some(index);
+                    }
+                    continue;
+                }
+                let node = &self.nodes[to_visit.pop()?];
+                items = &node.items;
+                if let Some(first) = node.children {
+                    // a point on the line between children can be in more than one
+                    to_visit.extend(
+                        (first..first + 4).filter(|&child| inside(self.nodes[child].bounds, p)),
+                    );
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Vec3;
+    use rand::prelude::*;
+
+    #[test]
+    fn queries_find_every_triangle_covering_a_point() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut point = || Vec2 {
+            x: rng.random_range(-50.0..650.0),
+            y: rng.random_range(-50.0..650.0),
+        };
+        let triangles = (0..300)
+            .map(|i| {
+                // a few big ones among lots of small ones
+                let size = if i % 50 == 0 { 400.0 } else { 30.0 };
+                let corner = point();
+                Tri2::flat(
+                    [
+                        corner,
+                        corner + Vec2 { x: size, y: 0.0 },
+                        corner + Vec2 { x: 0.0, y: size },
+                    ],
+                    Vec3::new(1.0, 1.0, 1.0),
+                )
+            })
+            .collect::<Vec<Tri2>>();
+        let index = SceneIndex::new(&triangles);
+        for _ in 0..1000 {
+            let p = point();
+            let candidates = index.query(p).collect::<Vec<usize>>();
+            for (i, triangle) in triangles.iter().enumerate() {
+                if triangle.contains(p, 0.0) {
+                    assert!(candidates.contains(&i));
+                }
+            }
+            // it actually narrows things down
+            assert!(candidates.len() < triangles.len());
+        }
+        assert_eq!(
+            SceneIndex::new(&[]).query(Vec2 { x: 1.0, y: 1.0 }).count(),
+            0
+        );
+    }
+}
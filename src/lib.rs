@@ -1,12 +1,58 @@
 //! software rasterizer
 #![warn(missing_docs, clippy::missing_docs_in_private_items)]
 
+use error_stack::Report;
 use rand::prelude::*;
-use std::ops::{Mul, Sub};
+use std::ops::{Add, AddAssign, Mul, Sub};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use geometry::clip_polygon;
+use lighting::{LitTri, Lighting};
+use raster::{Band, EdgeSetup, Frame, Projected, RenderSettings};
 use tracing::{event, span, Level};
 
+mod blend;
+mod camera;
+mod convert;
+mod export;
+mod geometry;
+#[cfg(feature = "gltf")]
+mod gltf;
+mod index;
+mod lighting;
+mod material;
+mod math;
+mod mesh;
+mod pixel;
+mod raster;
+mod shader;
+mod texture;
+
+pub use blend::{BlendMode, blend_buffers};
+pub use camera::Camera;
+pub use convert::rgb8_bytes;
+pub use export::{ChannelOrder, ExportError, RleError};
+pub use geometry::{convex_hull, delaunay};
+#[cfg(feature = "gltf")]
+pub use gltf::GltfError;
+pub use index::SceneIndex;
+pub use lighting::{Light, Specular};
+pub use material::Material;
+pub use math::{Mat3, Mat4};
+pub use raster::{DebugMode, DepthFunc, RenderStats, SamplePattern};
+pub use shader::{FlatShader, Shader};
+pub use texture::{AddressMode, RenderTarget, Texture};
+pub use pixel::Pixel;
+pub use mesh::{
+    FrontFace, Mesh, Tri3, ray_triangle_intersect, triangle_fan, triangle_strip, weld_triangles,
+};
+/// The SDL3 bindings, for writing handlers for [`Scene::display_sdl3_with_handler`].
+#[cfg(feature = "sdl3")]
+pub use sdl3;
+
 /// A position, rotation, or something else.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
 pub struct Vec3 {
     /// The X component.
     x: f64,
@@ -45,7 +91,192 @@ impl Vec3 {
     }
 }
 
+impl Add for Vec3 {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+        }
+    }
+}
+
+impl AddAssign for Vec3 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Mul for Vec3 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            x: self.x * rhs.x,
+            y: self.y * rhs.y,
+            z: self.z * rhs.z,
+        }
+    }
+}
+
+impl Mul<f64> for Vec3 {
+    type Output = Self;
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
+
+impl std::iter::Sum for Vec3 {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::new(0.0, 0.0, 0.0), Add::add)
+    }
+}
+
+impl<'a> std::iter::Sum<&'a Vec3> for Vec3 {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+/// Multiplies component-wise.
+impl std::iter::Product for Vec3 {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::new(1.0, 1.0, 1.0), Mul::mul)
+    }
+}
+
+impl<'a> std::iter::Product<&'a Vec3> for Vec3 {
+    fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().product()
+    }
+}
+
 impl Vec3 {
+    /// Create a new Vec3.
+    pub const fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+    /// Take the dot product of two Vec3s.
+    pub fn dot(self, other: Self) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+    /// Take the cross product of two Vec3s.
+    pub fn cross(self, other: Self) -> Self {
+        Self {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+    /// Reflect the vector off a surface with the unit normal `normal`.
+    pub fn reflect(self, normal: Self) -> Self {
+        self - normal * (2.0 * self.dot(normal))
+    }
+    /// The length of the vector.
+    pub fn length(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+    /// The vector scaled to a length of 1. Zero vectors are returned unchanged.
+    pub fn normalize(self) -> Self {
+        let length = self.length();
+        if length == 0.0 { self } else { self * (1.0 / length) }
+    }
+    /// Two unit vectors perpendicular to this one and to each other, e.g. the tangent and
+    /// bitangent for a normal. `self` should be normalized. This uses the branchless method from
+    /// Duff et al., "Building an Orthonormal Basis, Revisited", which is stable for every
+    /// direction.
+    pub fn orthonormal_basis(self) -> (Vec3, Vec3) {
+        let sign = 1.0f64.copysign(self.z);
+        let a = -1.0 / (sign + self.z);
+        let b = self.x * self.y * a;
+        (
+            Vec3::new(1.0 + sign * self.x * self.x * a, sign * b, -sign * self.x),
+            Vec3::new(b, sign + self.y * self.y * a, -self.y),
+        )
+    }
+    /// Convert an sRGB-encoded color to linear, with the piecewise sRGB transfer function.
+    pub fn srgb_to_linear(self) -> Self {
+        /// Convert one channel.
+        fn channel(value: f64) -> f64 {
+            if value <= 0.04045 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        }
+        self.map(channel)
+    }
+    /// Convert a linear color to sRGB, with the piecewise sRGB transfer function.
+    pub fn linear_to_srgb(self) -> Self {
+        /// Convert one channel.
+        fn channel(value: f64) -> f64 {
+            if value <= 0.0031308 {
+                value * 12.92
+            } else {
+                1.055 * value.powf(1.0 / 2.4) - 0.055
+            }
+        }
+        self.map(channel)
+    }
+    /// Apply `f` to each component.
+    pub fn map(self, f: impl Fn(f64) -> f64) -> Self {
+        Self::new(f(self.x), f(self.y), f(self.z))
+    }
+    /// Raise each component to the power `e`.
+    pub fn powf(self, e: f64) -> Self {
+        self.map(|value| value.powf(e))
+    }
+    /// Take the square root of each component.
+    pub fn sqrt(self) -> Self {
+        self.map(f64::sqrt)
+    }
+    /// Are all the components finite (not NaN or infinite)?
+    pub fn is_finite(self) -> bool {
+        self.x.is_finite() && self.y.is_finite() && self.z.is_finite()
+    }
+    /// Is any component NaN?
+    pub fn is_nan(self) -> bool {
+        self.x.is_nan() || self.y.is_nan() || self.z.is_nan()
+    }
+    /// Is any component infinite?
+    pub fn is_infinite(self) -> bool {
+        self.x.is_infinite() || self.y.is_infinite() || self.z.is_infinite()
+    }
+    /// Replace the components that aren't finite (NaN or infinite) with 0.0.
+    pub fn nan_to_zero(self) -> Self {
+        self.map(|value| if value.is_finite() { value } else { 0.0 })
+    }
+    /// The relative luminance of the color (Rec. 709 weights).
+    pub fn luminance(self) -> f64 {
+        0.2126 * self.r() + 0.7152 * self.g() + 0.0722 * self.b()
+    }
+    /// Convert the color to 8-bit RGB.
+    pub fn to_rgb8(self) -> [u8; 3] {
+        [
+            (self.r() * 256.0) as u8,
+            (self.g() * 256.0) as u8,
+            (self.b() * 256.0) as u8,
+        ]
+    }
+    /// Convert the color to 16-bit RGB, clamping it to 0.0 to 1.0.
+    pub fn to_rgb16(self) -> [u16; 3] {
+        [self.r(), self.g(), self.b()].map(|value| (value.clamp(0.0, 1.0) * 65535.0).round() as u16)
+    }
     /// Get the red component (maps to X).
     pub fn r(self) -> f64 {
         self.x
@@ -74,6 +305,84 @@ impl Vec3 {
     }
 }
 
+impl Vec3 {
+    /// The bit patterns of the components, with -0.0 treated as 0.0 and every NaN treated as the
+    /// same NaN, so equal-looking vectors have equal bits.
+    pub fn to_bits(self) -> [u64; 3] {
+        /// Normalize and convert one component.
+        fn bits(value: f64) -> u64 {
+            if value.is_nan() {
+                f64::NAN.to_bits()
+            } else if value == 0.0 {
+                0.0f64.to_bits()
+            } else {
+                value.to_bits()
+            }
+        }
+        [bits(self.x), bits(self.y), bits(self.z)]
+    }
+}
+
+/// A Vec3 that can be hashed and compared for equality by its bits (see [`Vec3::to_bits`]),
+/// e.g. for deduplicating vertices.
+#[derive(Debug, Clone, Copy)]
+pub struct HashableVec3(pub Vec3);
+
+impl PartialEq for HashableVec3 {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for HashableVec3 {}
+
+impl std::hash::Hash for HashableVec3 {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+impl From<Vec3> for HashableVec3 {
+    fn from(value: Vec3) -> Self {
+        Self(value)
+    }
+}
+
+/// Define swizzle methods on Vec3 that return a Vec3 or Vec2 of the named components.
+macro_rules! swizzles {
+    ($($name:ident => Vec3($a:ident, $b:ident, $c:ident)),* ; $($name2:ident => Vec2($x:ident, $y:ident)),*) => {
+        impl Vec3 {
+            $(
+                #[doc = concat!("Get the (", stringify!($a), ", ", stringify!($b), ", ", stringify!($c), ") components as a Vec3.")]
+                pub fn $name(self) -> Vec3 {
+                    Vec3::new(self.$a, self.$b, self.$c)
+                }
+            )*
+            $(
+                #[doc = concat!("Get the (", stringify!($x), ", ", stringify!($y), ") components as a Vec2.")]
+                pub fn $name2(self) -> Vec2 {
+                    Vec2 { x: self.$x, y: self.$y }
+                }
+            )*
+        }
+    };
+}
+
+swizzles! {
+    xyz => Vec3(x, y, z),
+    xzy => Vec3(x, z, y),
+    yxz => Vec3(y, x, z),
+    yzx => Vec3(y, z, x),
+    zxy => Vec3(z, x, y),
+    zyx => Vec3(z, y, x);
+    xy => Vec2(x, y),
+    xz => Vec2(x, z),
+    yx => Vec2(y, x),
+    yz => Vec2(y, z),
+    zx => Vec2(z, x),
+    zy => Vec2(z, y)
+}
+
 /// A position, rotation, or something else.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Vec2 {
@@ -83,6 +392,16 @@ pub struct Vec2 {
     pub y: f64,
 }
 
+impl Add for Vec2 {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+
 impl Sub for Vec2 {
     type Output = Self;
     fn sub(self, rhs: Self) -> Self {
@@ -113,11 +432,61 @@ impl Mul<f64> for Vec2 {
     }
 }
 
+impl std::iter::Sum for Vec2 {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self { x: 0.0, y: 0.0 }, Add::add)
+    }
+}
+
+impl<'a> std::iter::Sum<&'a Vec2> for Vec2 {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+/// Multiplies component-wise.
+impl std::iter::Product for Vec2 {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self { x: 1.0, y: 1.0 }, Mul::mul)
+    }
+}
+
+impl<'a> std::iter::Product<&'a Vec2> for Vec2 {
+    fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().product()
+    }
+}
+
 impl Vec2 {
+    /// Are both components finite (not NaN or infinite)?
+    pub fn is_finite(self) -> bool {
+        self.x.is_finite() && self.y.is_finite()
+    }
+    /// Is either component NaN?
+    pub fn is_nan(self) -> bool {
+        self.x.is_nan() || self.y.is_nan()
+    }
+    /// Is either component infinite?
+    pub fn is_infinite(self) -> bool {
+        self.x.is_infinite() || self.y.is_infinite()
+    }
+    /// Replace the components that aren't finite (NaN or infinite) with 0.0.
+    pub fn nan_to_zero(self) -> Self {
+        let zeroed = |value: f64| if value.is_finite() { value } else { 0.0 };
+        Self {
+            x: zeroed(self.x),
+            y: zeroed(self.y),
+        }
+    }
     /// Take the dot product of two Vec2s.
     pub fn dot(self, other: Self) -> f64 {
         self.x * other.x + self.y * other.y
     }
+    /// The vector scaled to a length of 1. Zero vectors are returned unchanged.
+    pub fn normalize(self) -> Self {
+        let length = self.dot(self).sqrt();
+        if length == 0.0 { self } else { self * (1.0 / length) }
+    }
     /// Turn the vector by 90 degrees clockwise.
     pub fn clockwise90(self) -> Self {
         Self {
@@ -125,6 +494,19 @@ impl Vec2 {
             y: -self.x,
         }
     }
+    /// The part of the vector pointing along `axis`. Projecting onto a zero axis gives zero.
+    pub fn project_onto(self, axis: Vec2) -> Vec2 {
+        let length_squared = axis.dot(axis);
+        if length_squared == 0.0 {
+            return Vec2 { x: 0.0, y: 0.0 };
+        }
+        axis * (self.dot(axis) / length_squared)
+    }
+    /// The part of the vector perpendicular to `axis`, so that
+    /// `v.project_onto(axis) + v.reject_from(axis) == v`.
+    pub fn reject_from(self, axis: Vec2) -> Vec2 {
+        self - self.project_onto(axis)
+    }
 
     /// Is the provided point p on the right side of the line?
     pub fn point_on_right_line(self, b: Vec2, p: Vec2) -> bool {
@@ -133,6 +515,65 @@ impl Vec2 {
 
         ap.dot(ab_perp) >= 0.0
     }
+    /// The pixel containing the point, rounding both components down. Components outside the
+    /// range of an `i32` saturate, and NaN becomes 0.
+    pub fn floor_to_ivec2(self) -> IVec2 {
+        IVec2 {
+            x: self.x.floor() as i32,
+            y: self.y.floor() as i32,
+        }
+    }
+    /// Like [`Vec2::floor_to_ivec2`], but rounding both components up.
+    pub fn ceil_to_ivec2(self) -> IVec2 {
+        IVec2 {
+            x: self.x.ceil() as i32,
+            y: self.y.ceil() as i32,
+        }
+    }
+}
+
+/// A pixel position on the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct IVec2 {
+    /// The X component.
+    pub x: i32,
+    /// The Y component.
+    pub y: i32,
+}
+
+impl IVec2 {
+    /// The position of the pixel as a Vec2.
+    pub fn to_vec2(self) -> Vec2 {
+        Vec2 {
+            x: self.x as f64,
+            y: self.y as f64,
+        }
+    }
+}
+
+/// Convert barycentric weights `w` relative to the triangle `tri` to a point.
+pub fn bary_to_point(tri: [Vec2; 3], w: [f64; 3]) -> Vec2 {
+    Vec2 {
+        x: tri[0].x * w[0] + tri[1].x * w[1] + tri[2].x * w[2],
+        y: tri[0].y * w[0] + tri[1].y * w[1] + tri[2].y * w[2],
+    }
+}
+
+/// Convert a point to barycentric weights relative to the triangle `tri`. Returns `None` if the
+/// triangle is degenerate.
+pub fn point_to_bary(tri: [Vec2; 3], p: Vec2) -> Option<[f64; 3]> {
+    let ab = tri[1] - tri[0];
+    let ac = tri[2] - tri[0];
+    let ap = p - tri[0];
+
+    let denominator = ab.x * ac.y - ac.x * ab.y;
+    if denominator.abs() < DEGENERATE_AREA {
+        return None;
+    }
+
+    let v = (ap.x * ac.y - ac.x * ap.y) / denominator;
+    let w = (ab.x * ap.y - ap.x * ab.y) / denominator;
+    Some([1.0 - v - w, v, w])
 }
 
 impl rand::distr::Distribution<Vec2> for rand::distr::StandardUniform {
@@ -157,21 +598,22 @@ impl rand::distr::Distribution<Vec3> for rand::distr::StandardUniform {
 #[cfg(feature = "image_types")]
 impl From<Vec3> for image::Rgb<u8> {
     fn from(val: Vec3) -> Self {
-        image::Rgb([
-            (val.r() * 256.0) as u8,
-            (val.g() * 256.0) as u8,
-            (val.b() * 256.0) as u8,
-        ])
+        image::Rgb(val.to_rgb8())
     }
 }
 
+/// Triangles with an absolute area below this are considered degenerate.
+const DEGENERATE_AREA: f64 = 1e-9;
+
 /// A 2D triangle.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Tri2 {
     /// The points of the triangle.
     pub points: [Vec2; 3],
-    /// The COLOR
-    pub color: Vec3,
+    /// The color at each point, interpolated across the triangle.
+    pub colors: [Vec3; 3],
+    /// The opacity, from 0.0 (invisible) to 1.0 (opaque).
+    pub alpha: f64,
 }
 
 impl Mul<f64> for Tri2 {
@@ -183,12 +625,39 @@ impl Mul<f64> for Tri2 {
                 self.points[1] * rhs,
                 self.points[2] * rhs,
             ],
-            color: self.color,
+            colors: self.colors,
+            alpha: self.alpha,
         }
     }
 }
 
 impl Tri2 {
+    /// An opaque triangle with one color everywhere.
+    pub fn flat(points: [Vec2; 3], color: Vec3) -> Self {
+        Self {
+            points,
+            colors: [color; 3],
+            alpha: 1.0,
+        }
+    }
+    /// Does the triangle have the same color at every point?
+    pub fn is_flat(self) -> bool {
+        self.colors[0] == self.colors[1] && self.colors[1] == self.colors[2]
+    }
+    /// The average of the colors at the points.
+    pub fn average_color(self) -> Vec3 {
+        (self.colors[0] + self.colors[1] + self.colors[2]) * (1.0 / 3.0)
+    }
+    /// The color at a point, interpolated between the colors at the points of the triangle.
+    pub fn color_at(self, p: Vec2) -> Vec3 {
+        if self.is_flat() {
+            return self.colors[0];
+        }
+        match point_to_bary(self.points, p) {
+            Some([u, v, w]) => self.colors[0] * u + self.colors[1] * v + self.colors[2] * w,
+            None => self.colors[0],
+        }
+    }
     /// Is the provided point inside the triangle?
     pub fn inside(self, point: Vec2) -> bool {
         let side_ab = self.points[0].point_on_right_line(self.points[1], point);
@@ -197,6 +666,40 @@ impl Tri2 {
 
         side_ab == side_bc && side_bc == side_ca
     }
+    /// Is the provided point inside the triangle, counting points whose barycentric weights are
+    /// within `epsilon` of the edges as inside? Degenerate triangles contain nothing.
+    pub fn contains(self, p: Vec2, epsilon: f64) -> bool {
+        point_to_bary(self.points, p).is_some_and(|weights| weights.iter().all(|&w| w >= -epsilon))
+    }
+    /// The signed area of the triangle. Positive when the points are in clockwise order (with Y
+    /// pointing down).
+    pub fn signed_area(self) -> f64 {
+        let [a, b, c] = self.points;
+        ((b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y)) / 2.0
+    }
+    /// The distance from the point to the nearest edge of the triangle, positive inside and
+    /// negative outside. Outside, near the corners, this underestimates the distance.
+    pub fn signed_distance(self, p: Vec2) -> f64 {
+        let orientation = self.signed_area().signum();
+        (0..3)
+            .map(|i| {
+                let a = self.points[i];
+                let b = self.points[(i + 1) % 3];
+                let edge = b - a;
+                let ap = p - a;
+                (edge.x * ap.y - edge.y * ap.x) / edge.dot(edge).sqrt() * orientation
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+    /// Roughly how much of a `width` by `height` screen the triangle covers, from 0.0 to 1.0,
+    /// without rasterizing it. Parts of the triangle off the screen still count.
+    pub fn screen_coverage(self, width: usize, height: usize) -> f64 {
+        (self.signed_area().abs() / (width * height) as f64).clamp(0.0, 1.0)
+    }
+    /// Does the triangle have (nearly) zero area, i.e. coincident or collinear points?
+    pub fn is_degenerate(self) -> bool {
+        self.signed_area().abs() < DEGENERATE_AREA
+    }
     /// Returns the bounding box of the triangle in a pair of coordinates (top-left, and
     /// bottom-right).
     pub fn bounding_box(self) -> (Vec2, Vec2) {
@@ -210,24 +713,296 @@ impl Tri2 {
         };
         (top_left, bottom_right)
     }
+    /// Are all the points finite (not NaN or infinite)?
+    pub fn is_finite(self) -> bool {
+        self.points.iter().all(|point| point.is_finite())
+    }
+    /// Cut the triangle down to the rectangle from `min` to `max` (e.g. the screen), as up to 5
+    /// triangles fanning out from one corner. Each piece has the triangle's colors at its points,
+    /// so they look the same as the part of the triangle they cover with [`FlatShader`]. Empty if
+    /// the triangle is entirely outside the rectangle, or degenerate.
+    pub fn clip_to_rect(self, min: Vec2, max: Vec2) -> Vec<Tri2> {
+        if self.is_degenerate() || !self.is_finite() {
+            return Vec::new();
+        }
+        let polygon = clip_polygon(&self.points, min, max);
+        (2..polygon.len())
+            .map(|i| {
+                let points = [polygon[0], polygon[i - 1], polygon[i]];
+                Tri2 {
+                    points,
+                    colors: points.map(|point| self.color_at(point)),
+                    alpha: self.alpha,
+                }
+            })
+            .filter(|piece| !piece.is_degenerate())
+            .collect()
+    }
+    /// Precompute the edges of the triangle for faster repeated [`inside`](Tri2::inside) tests.
+    pub fn prepared(self) -> PreparedTri {
+        let edge = |a: Vec2, b: Vec2| {
+            let perp = (b - a).clockwise90();
+            (perp, a.dot(perp))
+        };
+        let [a, b, c] = self.points;
+        let [(perp_ab, base_ab), (perp_bc, base_bc), (perp_ca, base_ca)] =
+            [edge(a, b), edge(b, c), edge(c, a)];
+        PreparedTri {
+            perps: [perp_ab, perp_bc, perp_ca],
+            bases: [base_ab, base_bc, base_ca],
+        }
+    }
+}
+
+/// A triangle with its edge perpendiculars precomputed, made by [`Tri2::prepared`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PreparedTri {
+    /// The perpendicular of each edge, pointing to its right.
+    perps: [Vec2; 3],
+    /// The dot product of each edge's perpendicular with its first point.
+    bases: [f64; 3],
+}
+
+impl PreparedTri {
+    /// Is the provided point inside the triangle? Same as [`Tri2::inside`].
+    pub fn inside(self, point: Vec2) -> bool {
+        let side_ab = point.dot(self.perps[0]) >= self.bases[0];
+        let side_bc = point.dot(self.perps[1]) >= self.bases[1];
+        let side_ca = point.dot(self.perps[2]) >= self.bases[2];
+
+        side_ab == side_bc && side_bc == side_ca
+    }
 }
 
 impl rand::distr::Distribution<Tri2> for rand::distr::StandardUniform {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Tri2 {
         Tri2 {
             points: [self.sample(rng), self.sample(rng), self.sample(rng)],
-            color: self.sample(rng),
+            colors: [self.sample(rng); 3],
+            alpha: 1.0,
+        }
+    }
+}
+
+/// How 2D triangles are moved onto the output: [`Scene::origin`] and [`Scene::pixel_perfect`].
+type ScreenMapping = (Origin, bool);
+
+/// Where (0, 0) is for 2D triangles, and which way Y goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Origin {
+    /// The top-left corner, with Y going down like rows of pixels.
+    #[default]
+    TopLeft,
+    /// The bottom-left corner, with Y going up like in math.
+    BottomLeft,
+}
+
+/// The byte layout of pixels uploaded to a texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PixelFormat {
+    /// 8-bit red, green, blue.
+    #[default]
+    Rgb24,
+    /// 8-bit blue, green, red.
+    Bgr24,
+    /// 8-bit red, green, blue, alpha.
+    Rgba32,
+    /// 8-bit blue, green, red, alpha.
+    Bgra32,
+}
+
+impl PixelFormat {
+    /// The number of bytes in one pixel.
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Rgb24 | Self::Bgr24 => 3,
+            Self::Rgba32 | Self::Bgra32 => 4,
+        }
+    }
+    /// Pack a color into bytes in this format. Only the first [`PixelFormat::bytes_per_pixel`]
+    /// bytes are used; alpha is always opaque.
+    pub fn pack(self, color: Vec3) -> [u8; 4] {
+        self.pack_rgb8(color.to_rgb8())
+    }
+    /// Pack an already converted 8-bit color into bytes in this format.
+    fn pack_rgb8(self, [r, g, b]: [u8; 3]) -> [u8; 4] {
+        match self {
+            Self::Rgb24 => [r, g, b, 0],
+            Self::Bgr24 => [b, g, r, 0],
+            Self::Rgba32 => [r, g, b, 255],
+            Self::Bgra32 => [b, g, r, 255],
         }
     }
+    /// The matching SDL pixel format.
+    #[cfg(feature = "sdl3")]
+    fn to_sdl3(self) -> sdl3::pixels::PixelFormat {
+        use sdl3::sys::pixels;
+
+        sdl3::pixels::PixelFormat::try_from(match self {
+            Self::Rgb24 => pixels::SDL_PIXELFORMAT_RGB24,
+            Self::Bgr24 => pixels::SDL_PIXELFORMAT_BGR24,
+            Self::Rgba32 => pixels::SDL_PIXELFORMAT_RGBA32,
+            Self::Bgra32 => pixels::SDL_PIXELFORMAT_BGRA32,
+        })
+        .unwrap()
+    }
+}
+
+/// The weight of a translucent fragment for weighted blended order-independent transparency,
+/// from McGuire and Bavoil's paper. `depth` is from 0.0 (near) to 1.0 (far).
+fn oit_weight(alpha: f64, depth: f64) -> f64 {
+    alpha * (0.03 / (1e-5 + depth.powi(4))).clamp(1e-2, 3e3)
+}
+
+/// An error from combining the outputs of two Scenes with different sizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeMismatchError;
+
+impl std::fmt::Display for SizeMismatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("scene outputs have different sizes")
+    }
+}
+
+impl std::error::Error for SizeMismatchError {}
+
+/// An error from using a triangle index past the end of a Scene's triangles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TriangleIndexError;
+
+impl std::fmt::Display for TriangleIndexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("triangle index out of bounds")
+    }
+}
+
+impl std::error::Error for TriangleIndexError {}
+
+/// The length of each arm of the crosshair in the SDL3 window, in pixels.
+#[cfg(feature = "sdl3")]
+const CROSSHAIR_SIZE: f32 = 10.0;
+
+/// What an event handler passed to [`Scene::display_sdl3_with_handler`] wants to happen next.
+#[cfg(feature = "sdl3")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ControlFlow {
+    /// Keep going, with the window as it is.
+    #[default]
+    Continue,
+    /// Show the Scene's output again, e.g. after rendering.
+    Redraw,
+    /// Close the window.
+    Quit,
 }
 
-/// A scene.
+/// A scene, with its output stored as `P` pixels.
 #[derive(Debug, Clone, PartialEq)]
-pub struct Scene {
-    /// The output.
-    output: Box<[[Vec3; 600]; 600]>,
+pub struct Scene<P = Vec3> {
+    /// The width of the output.
+    width: usize,
+    /// The height of the output.
+    height: usize,
+    /// The output, row by row.
+    output: Vec<P>,
+    /// The depth of each pixel in the output, row by row. Cleared to infinity.
+    depth: Vec<f64>,
+    /// The color the color buffer is cleared to.
+    pub clear_color: Vec3,
+    /// The image the color buffer is cleared to instead of [`Scene::clear_color`], if there is
+    /// one.
+    background: Option<Texture>,
+    /// Whether [`Scene::render`] clears the color and depth buffers first.
+    pub clear_on_render: bool,
     /// The triangle.
     triangles: Vec<Tri2>,
+    /// The 3D triangles.
+    triangles_3d: Vec<Tri3>,
+    /// The meshes, drawn at the level of detail that suits their size on the screen.
+    meshes: Vec<Mesh>,
+    /// The screen areas in pixels below which meshes switch to their next lower level of detail.
+    /// A mesh whose bounds cover less than the first threshold uses its first LOD, less than the
+    /// second its second LOD, and so on, as far as it has LODs.
+    pub lod_thresholds: Vec<f64>,
+    /// The longest a 3D triangle's edges can be on the screen, in pixels, before it's split in
+    /// two at the middle of its longest edge, over and over until they're short enough (or it's
+    /// been split 10 times deep). Smaller triangles make the normals and depths interpolated
+    /// across the screen closer to perspective correct. Textured triangles aren't split, since
+    /// their UVs come from the triangle's corners. `None` turns it off.
+    pub tessellation_threshold: Option<f64>,
+    /// The lights shining on the 3D triangles. With no lights, they're drawn unlit in their own
+    /// colors.
+    pub lights: Vec<Light>,
+    /// The materials 3D triangles can use, by index (see [`Tri3::material`]). The first one is
+    /// [`Material::default`] to begin with, which leaves triangles as they are.
+    pub materials: Vec<Material>,
+    /// The textures materials can use, by index (see [`Material::texture`]).
+    pub textures: Vec<Texture>,
+    /// The light that reaches every 3D surface from every direction, when there are lights.
+    pub ambient_light: Vec3,
+    /// Which winding order marks the front face of 3D triangles.
+    pub front_face: FrontFace,
+    /// Whether 3D triangles facing away from the camera are skipped.
+    pub backface_culling: bool,
+    /// The camera the 3D triangles are seen through.
+    pub camera: Camera,
+    /// The depths the near and far planes map to.
+    depth_range: (f64, f64),
+    /// Which depths pass the depth test.
+    depth_func: DepthFunc,
+    /// The constant and slope-scaled depth bias, see [`Scene::set_depth_bias`].
+    depth_bias: (f64, f64),
+    /// Whether the depth range is reversed, see [`Scene::set_reversed_z`].
+    reversed_z: bool,
+    /// Whether [`Scene::add_triangle`] rejects degenerate triangles instead of only logging them.
+    pub reject_degenerate: bool,
+    /// Whether [`Scene::add_triangle`] clamps the colors of triangles to 0.0 to 1.0, for
+    /// displays that can't show anything brighter.
+    pub clamp_input_colors: bool,
+    /// Where (0, 0) is for 2D triangles and lines. The output itself is always stored from the
+    /// top row down, so with [`Origin::BottomLeft`] the point (x, y) is at pixel
+    /// (x, height - 1 - y).
+    pub origin: Origin,
+    /// Whether translucent triangles use weighted blended order-independent transparency.
+    oit: bool,
+    /// The number of samples per pixel for multisample antialiasing, 1 for none.
+    msaa_samples: usize,
+    /// Where the samples are in each pixel for multisample antialiasing.
+    sample_pattern: SamplePattern,
+    /// Whether translucent 2D triangles cover some of the samples of each pixel with multisample
+    /// antialiasing, as many as their alpha says, instead of blending, like cutouts for foliage.
+    /// Doesn't apply to order-independent transparency, or without multisampling.
+    pub alpha_to_coverage: bool,
+    /// What triangles show instead of their colors, for finding rendering bugs. Textures and
+    /// lighting are skipped unless it's [`DebugMode::Off`].
+    pub debug_mode: DebugMode,
+    /// Whether triangle edges are antialiased, with pixels near an edge partly covered depending
+    /// on their distance from it. Doesn't apply to translucent triangles with order-independent
+    /// transparency.
+    pub edge_antialiasing: bool,
+    /// Whether the points of 2D triangles are rounded to whole pixels and drawn without edge
+    /// antialiasing or multisampling, for crisp UI. With the top-left fill rule, triangles
+    /// sharing an edge then cover every pixel along it exactly once, so rectangles built from
+    /// triangles tile without gaps or overlaps. 3D triangles are drawn as usual.
+    pub pixel_perfect: bool,
+    /// The counters from the last render.
+    stats: RenderStats,
+    /// What the last [`Scene::render`] drew: the frame, clear color, and whether it cleared.
+    last_render: Option<(Frame, Vec3, bool)>,
+    /// Whether the buffers have been changed other than by [`Scene::render`] since it last ran.
+    dirty: bool,
+    /// The edge equations of the 2D triangles (after culling) from the last render, and the
+    /// origin and [`Scene::pixel_perfect`] they were set up with.
+    edge_cache: Option<(ScreenMapping, Vec<Option<EdgeSetup>>)>,
+    /// Whether the 2D triangles might have changed since the edge equations were cached.
+    triangles_changed: bool,
+    /// The index of the 2D triangles for [`Scene::hit_test`], built when it's first needed.
+    index: OnceLock<SceneIndex>,
+    /// The pixel format used for the SDL texture.
+    pub pixel_format: PixelFormat,
+    /// The order of the color channels in exported PNGs and [`Scene::to_rgba8`].
+    pub channel_order: ChannelOrder,
+    /// The color the SDL window is cleared to before the output is drawn on it.
+    pub window_clear_color: Vec3,
 }
 
 impl Default for Scene {
@@ -236,163 +1011,3540 @@ impl Default for Scene {
     }
 }
 
-/// default output (all black)
-static DEFAULT_OUTPUT: [[Vec3; 600]; 600] = [[Vec3 {
-                    x: 0.0,
-                    y: 0.0,
-                    z: 0.0,
-                }; 600]; 600];
-
 impl Scene {
-    /// Create a new Scene.
+    /// Create a new 600x600 Scene with no triangles.
+    pub fn empty() -> Self {
+        Self::with_size(600, 600)
+    }
+    /// Create a new Scene with no triangles and an output of the given size.
+    pub fn with_size(width: usize, height: usize) -> Self {
+        Self::sized(width, height)
+    }
+    /// Create a new Scene with 20 random triangles.
     pub fn new() -> Self {
+        Self::new_with_rng(&mut rand::rng(), 20)
+    }
+    /// Create a new Scene with `count` random triangles generated by `rng`, spread over the
+    /// whole output.
+    pub fn new_with_rng(rng: &mut impl Rng, count: usize) -> Self {
+        let scene = Self::empty();
+        let scale = scene.width.min(scene.height) as f64;
+        scene.with_random_triangles(rng, count, scale)
+    }
+    /// Create a new Scene with `count` random triangles with points from (0, 0) to
+    /// (`scale`, `scale`), generated from `seed` so the same parameters always give the same
+    /// Scene. A `scale` of 600.0 fills the output.
+    pub fn random_params(count: usize, scale: f64, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        Self::empty().with_random_triangles(&mut rng, count, scale)
+    }
+    /// Add `count` random triangles generated by `rng`, with points from (0, 0) to (`scale`,
+    /// `scale`).
+    fn with_random_triangles(mut self, rng: &mut impl Rng, count: usize, scale: f64) -> Self {
         let span = span!(Level::TRACE, "initalize_scene");
         let _enter = span.enter();
+        self.triangles = (0..count)
+            .map(|_| rng.random::<Tri2>() * scale)
+            .collect::<Vec<Tri2>>();
+        self
+    }
+}
+
+impl<P: Pixel> Scene<P> {
+    /// Create a new Scene with no triangles and an output of the given size, for any pixel type
+    /// (e.g. `Scene::<[u8; 3]>::sized(width, height)`).
+    pub fn sized(width: usize, height: usize) -> Self {
         Self {
-            output: Box::new(
-                DEFAULT_OUTPUT
-            ),
-            triangles: vec![
-                Tri2 {
-                    points: [
-                        Vec2 { x: 0.0, y: 0.0 },
-                        Vec2 { x: 0.0, y: 0.0 },
-                        Vec2 { x: 0.0, y: 0.0 },
-                    ],
-                    color: Vec3 {
-                        x: 0.0,
-                        y: 0.0,
-                        z: 0.0
-                    }
-                };
-                20
-            ]
+            width,
+            height,
+            output: vec![P::from_vec3(Vec3::new(0.0, 0.0, 0.0)); width * height],
+            depth: vec![f64::INFINITY; width * height],
+            clear_color: Vec3::new(0.0, 0.0, 0.0),
+            background: None,
+            clear_on_render: false,
+            triangles: Vec::new(),
+            triangles_3d: Vec::new(),
+            meshes: Vec::new(),
+            lod_thresholds: vec![64.0 * 64.0, 32.0 * 32.0, 16.0 * 16.0, 8.0 * 8.0],
+            tessellation_threshold: None,
+            lights: Vec::new(),
+            materials: vec![Material::default()],
+            textures: Vec::new(),
+            ambient_light: Vec3::new(0.1, 0.1, 0.1),
+            front_face: FrontFace::default(),
+            backface_culling: false,
+            camera: Camera::default(),
+            depth_range: (0.0, 1.0),
+            depth_func: DepthFunc::default(),
+            depth_bias: (0.0, 0.0),
+            reversed_z: false,
+            reject_degenerate: false,
+            clamp_input_colors: false,
+            origin: Origin::default(),
+            oit: false,
+            msaa_samples: 1,
+            sample_pattern: SamplePattern::default(),
+            alpha_to_coverage: false,
+            debug_mode: DebugMode::default(),
+            edge_antialiasing: false,
+            pixel_perfect: false,
+            stats: RenderStats::default(),
+            last_render: None,
+            dirty: true,
+            edge_cache: None,
+            triangles_changed: true,
+            index: OnceLock::new(),
+            pixel_format: PixelFormat::default(),
+            channel_order: ChannelOrder::default(),
+            window_clear_color: Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+    /// The triangles in this Scene.
+    pub fn triangles(&self) -> &[Tri2] {
+        &self.triangles
+    }
+    /// The triangles in this Scene, for moving them around.
+    pub fn triangles_mut(&mut self) -> &mut [Tri2] {
+        self.triangles_changed = true;
+        self.index = OnceLock::new();
+        &mut self.triangles
+    }
+    /// The combined bounding box of all triangles (top-left, and bottom-right), or `None` if
+    /// there aren't any.
+    pub fn bounds(&self) -> Option<(Vec2, Vec2)> {
+        self.triangles
             .iter()
-            .map(|_| rand::rng().random::<Tri2>() * 512.0)
-            .collect::<Vec<Tri2>>(),
+            .map(|triangle| triangle.bounding_box())
+            .reduce(|(top_left_a, bottom_right_a), (top_left_b, bottom_right_b)| {
+                (
+                    Vec2 {
+                        x: top_left_a.x.min(top_left_b.x),
+                        y: top_left_a.y.min(top_left_b.y),
+                    },
+                    Vec2 {
+                        x: bottom_right_a.x.max(bottom_right_b.x),
+                        y: bottom_right_a.y.max(bottom_right_b.y),
+                    },
+                )
+            })
+    }
+    /// Move every 2D triangle's points by `transform` (see [`Mat3::transform_point_2d`]).
+    pub fn transform_triangles(&mut self, transform: Mat3) {
+        for triangle in self.triangles_mut() {
+            for point in &mut triangle.points {
+                (*point) = transform.transform_point_2d(*point);
+            }
         }
     }
-    /// Render this Scene.
-    pub fn render(&mut self) {
-        let span = span!(Level::TRACE, "render_scene");
-        let _enter = span.enter();
-        
-        for triangle in &self.triangles {
-            let (top_left, bottom_right) = triangle.bounding_box();
-            event!(Level::TRACE, "calculated triangle bounding box: {top_left:#?}, {bottom_right:#?}");
+    /// Scale and move the 2D triangles, keeping their proportions, so they fill the output with
+    /// `padding` pixels spare on the sides they reach, and are centered along the other axis.
+    /// Does nothing if there aren't any, or they go off to infinity.
+    pub fn fit_to_view(&mut self, padding: f64) {
+        let Some((top_left, bottom_right)) = self.bounds() else {
+            return;
+        };
+        if !top_left.is_finite() || !bottom_right.is_finite() {
+            event!(Level::WARN, "can't fit non-finite triangles to the view");
+            return;
+        }
+        let size = bottom_right - top_left;
+        let available = Vec2 {
+            x: (self.width as f64 - padding * 2.0).max(0.0),
+            y: (self.height as f64 - padding * 2.0).max(0.0),
+        };
+        // a flat axis can be scaled by anything, so only the other one counts
+        let scale = [(size.x, available.x), (size.y, available.y)]
+            .into_iter()
+            .filter(|&(size, _)| size > 0.0)
+            .map(|(size, available)| available / size)
+            .reduce(f64::min)
+            .unwrap_or(1.0);
 
-            for (y, row) in self.output[top_left.x as usize..bottom_right.x as usize]
-                .iter_mut()
-                .enumerate()
+        let center = (top_left + bottom_right) * 0.5;
+        let view_center = Vec2 {
+            x: self.width as f64 / 2.0,
+            y: self.height as f64 / 2.0,
+        };
+        self.transform_triangles(
+            Mat3::translation_2d(view_center)
+                * Mat3::scale_2d(Vec2 { x: scale, y: scale })
+                * Mat3::translation_2d(center * -1.0),
+        );
+    }
+    /// The smallest box containing every 3D triangle and mesh vertex, as (minimum, maximum)
+    /// corners, or `None` if there aren't any.
+    pub fn bounds_3d(&self) -> Option<(Vec3, Vec3)> {
+        self.triangles_3d
+            .iter()
+            .flat_map(|triangle| triangle.points)
+            .chain(self.meshes.iter().flat_map(|mesh| mesh.vertices.iter().copied()))
+            .map(|point| (point, point))
+            .reduce(|(min_a, max_a), (min_b, max_b)| {
+                (
+                    Vec3::new(min_a.x.min(min_b.x), min_a.y.min(min_b.y), min_a.z.min(min_b.z)),
+                    Vec3::new(max_a.x.max(max_b.x), max_a.y.max(max_b.y), max_a.z.max(max_b.z)),
+                )
+            })
+    }
+    /// Add the triangles of `other` to this Scene.
+    pub fn merge(&mut self, other: &Scene<P>) {
+        self.extend_triangles(other.triangles.iter().copied());
+    }
+    /// Add the triangles of `other` to this Scene and combine its output into this one. Each
+    /// pixel keeps whichever side is nearer; when they're at the same depth (e.g. both 2D), the
+    /// pixel from `other` wins unless it's still `other`'s clear color.
+    pub fn merge_with_output(
+        &mut self,
+        other: &Scene<P>,
+    ) -> Result<(), Report<SizeMismatchError>> {
+        if (self.width, self.height) != (other.width, other.height) {
+            return Err(Report::new(SizeMismatchError).attach_printable(format!(
+                "{}x{} vs {}x{}",
+                self.width, self.height, other.width, other.height
+            )));
+        }
+
+        for ((color, depth), (&other_color, &other_depth)) in self
+            .output
+            .iter_mut()
+            .zip(&mut self.depth)
+            .zip(other.output.iter().zip(&other.depth))
+        {
+            if other_depth < *depth
+                || (other_depth == *depth && other_color != P::from_vec3(other.clear_color))
             {
-                for (x, color) in row[top_left.y as usize..bottom_right.y as usize]
-                    .iter_mut()
-                    .enumerate()
-                {
-                    if triangle.inside(Vec2 {
-                        x: x as f64,
-                        y: y as f64,
-                    }) {
-                        (*color) = triangle.color
-                    }
-                }
+                (*color) = other_color;
+                (*depth) = other_depth;
             }
         }
+        self.dirty = true;
+        self.merge(other);
+        Ok(())
     }
-    /// Display the texture on the window.
-    fn display_tex_sdl3(&self, texture: &mut sdl3::render::Texture) {
-        texture
-            .update(
-                None,
-                &self
-                    .output
-                    .iter()
-                    .flatten()
-                    .flat_map(|val| {
-                        [
-                            (val.r() * 256.0) as u8,
-                            (val.g() * 256.0) as u8,
-                            (val.b() * 256.0) as u8,
-                        ]
-                    })
-                    .collect::<Vec<u8>>(),
-                self.output[0].len() * 3,
-            )
-            .unwrap();
+    /// Add several triangles to this Scene, with the same checks as [`Scene::add_triangle`].
+    pub fn extend_triangles(&mut self, triangles: impl IntoIterator<Item = Tri2>) {
+        for triangle in triangles {
+            self.add_triangle(triangle);
+        }
     }
-    /// Display this rendered Scene.
-    #[cfg(feature = "sdl3")]
-    pub fn display_sdl3(&mut self) -> Result<(), impl std::error::Error> {
-        let sdl = sdl3::init()?;
+    /// Add a triangle to this Scene. Degenerate (zero-area) triangles are logged, and skipped if
+    /// [`Scene::reject_degenerate`] is set. The colors are clamped if
+    /// [`Scene::clamp_input_colors`] is set. Returns whether the triangle was added.
+    pub fn add_triangle(&mut self, mut triangle: Tri2) -> bool {
+        if triangle.is_degenerate() {
+            if self.reject_degenerate {
+                event!(Level::DEBUG, "rejected degenerate triangle: {triangle:?}");
+                return false;
+            }
+            event!(Level::WARN, "added degenerate triangle: {triangle:?}");
+        }
+        if self.clamp_input_colors {
+            triangle.colors = triangle
+                .colors
+                .map(|color| color.map(|channel| channel.clamp(0.0, 1.0)));
+        }
+        self.triangles.push(triangle);
+        self.triangles_changed = true;
+        self.index = OnceLock::new();
+        true
+    }
+    /// The 3D triangles in this Scene.
+    pub fn triangles_3d(&self) -> &[Tri3] {
+        &self.triangles_3d
+    }
+    /// The 3D triangles in this Scene, for moving them around.
+    pub fn triangles_3d_mut(&mut self) -> &mut [Tri3] {
+        &mut self.triangles_3d
+    }
+    /// Add a 3D triangle to this Scene.
+    pub fn add_triangle_3d(&mut self, triangle: Tri3) {
+        self.triangles_3d.push(triangle);
+    }
+    /// The meshes in this Scene.
+    pub fn meshes(&self) -> &[Mesh] {
+        &self.meshes
+    }
+    /// The meshes in this Scene, for moving them around.
+    pub fn meshes_mut(&mut self) -> &mut [Mesh] {
+        &mut self.meshes
+    }
+    /// Add a mesh to this Scene. Unlike triangles added with [`Scene::add_triangle_3d`], it's
+    /// drawn at the level of detail picked by [`Scene::lod_index`] each render.
+    pub fn add_mesh(&mut self, mesh: Mesh) {
+        self.meshes.push(mesh);
+    }
+    /// Which level of detail the mesh would be drawn at: 0 for the mesh itself, or 1 + the
+    /// index into [`Mesh::lods`], depending on how much of the screen its bounds cover (see
+    /// [`Scene::lod_thresholds`]). Meshes partly behind the camera use full detail.
+    pub fn lod_index(&self, mesh: &Mesh) -> usize {
+        let Some((min, max)) = mesh.bounds() else {
+            return 0;
+        };
+        let view_projection = self.view_projection();
+        let (width, height) = (self.width as f64, self.height as f64);
 
-        let mut canvas = sdl
-            .video()?
-            .window(
-                "ThreeD Window",
-                self.output[0].len() as u32,
-                self.output.len() as u32,
+        let mut top_left = Vec2 {
+            x: f64::INFINITY,
+            y: f64::INFINITY,
+        };
+        let mut bottom_right = Vec2 {
+            x: f64::NEG_INFINITY,
+            y: f64::NEG_INFINITY,
+        };
+        for i in 0..8 {
+            let corner = [
+                if i & 1 == 0 { min.x() } else { max.x() },
+                if i & 2 == 0 { min.y() } else { max.y() },
+                if i & 4 == 0 { min.z() } else { max.z() },
+                1.0,
+            ];
+            let [x, y, _, w] = view_projection.transform(corner);
+            if w <= 0.0 {
+                return 0;
+            }
+            let point = Vec2 {
+                x: (x / w + 1.0) / 2.0 * width,
+                y: (1.0 - y / w) / 2.0 * height,
+            };
+            top_left = Vec2 {
+                x: top_left.x.min(point.x),
+                y: top_left.y.min(point.y),
+            };
+            bottom_right = Vec2 {
+                x: bottom_right.x.max(point.x),
+                y: bottom_right.y.max(point.y),
+            };
+        }
+
+        let area = (bottom_right.x - top_left.x) * (bottom_right.y - top_left.y);
+        let level = self
+            .lod_thresholds
+            .iter()
+            .take_while(|&&threshold| area < threshold)
+            .count();
+        level.min(mesh.lods.len())
+    }
+    /// The mesh at the level of detail picked by [`Scene::lod_index`].
+    fn select_lod<'a>(&self, mesh: &'a Mesh) -> &'a Mesh {
+        match self.lod_index(mesh) {
+            0 => mesh,
+            level => &mesh.lods[level - 1],
+        }
+    }
+    /// The 3D triangles sorted back-to-front by the camera-space depth of their centroids, for
+    /// painter's algorithm style drawing.
+    pub fn triangles_sorted_by_depth(&self) -> Vec<&Tri3> {
+        let view = self.camera.view_matrix();
+        let mut triangles = self
+            .triangles_3d
+            .iter()
+            .map(|triangle| (view.transform_point(triangle.centroid()).z(), triangle))
+            .collect::<Vec<(f64, &Tri3)>>();
+        // the camera looks down -Z, so the farthest triangles have the lowest Z
+        triangles.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        triangles.into_iter().map(|(_, triangle)| triangle).collect()
+    }
+    /// Set the depths the near and far planes map to (0.0 and 1.0 by default). With
+    /// [`Scene::set_reversed_z`] they're swapped. If that turns the depth test around, the depth
+    /// buffer is cleared, since its depths would all be nearer than anything new.
+    pub fn set_depth_range(&mut self, near_value: f64, far_value: f64) {
+        let farthest = self.render_settings().farthest();
+        self.depth_range = (near_value, far_value);
+        if self.render_settings().farthest() != farthest {
+            self.clear_depth_buffer();
+        }
+    }
+    /// Reverse the depth range, with the near plane at the far value and the far plane at the
+    /// near value, or put it back. This spreads out float precision more evenly. The depth
+    /// buffer is cleared if it changes, like with [`Scene::set_depth_range`].
+    pub fn set_reversed_z(&mut self, reversed_z: bool) {
+        let farthest = self.render_settings().farthest();
+        self.reversed_z = reversed_z;
+        if self.render_settings().farthest() != farthest {
+            self.clear_depth_buffer();
+        }
+    }
+    /// Whether the depth range is reversed (see [`Scene::set_reversed_z`]).
+    pub fn reversed_z(&self) -> bool {
+        self.reversed_z
+    }
+    /// Set which depths pass the depth test and get drawn ([`DepthFunc::Less`] by default).
+    pub fn set_depth_func(&mut self, depth_func: DepthFunc) {
+        self.depth_func = depth_func;
+    }
+    /// Offset the depth of 3D triangles before the depth test, like OpenGL's polygon offset, to
+    /// stop coplanar triangles z-fighting. The offset is `slope` times the triangle's steepest
+    /// depth slope (the most its depth changes per pixel) plus `constant` times the smallest
+    /// difference a 24-bit depth buffer could tell apart (1/2^24 of the depth range). Positive
+    /// values push triangles away from the camera and negative values pull them towards it.
+    ///
+    /// The bias applies to everything drawn while it's set, so draw decals in a second pass
+    /// (with [`Scene::clear_on_render`] off) with e.g. `set_depth_bias(-1.0, -1.0)`.
+    pub fn set_depth_bias(&mut self, constant: f64, slope: f64) {
+        self.depth_bias = (constant, slope);
+    }
+    /// The settings used to rasterize this Scene.
+    fn render_settings(&self) -> RenderSettings {
+        let (near_value, far_value) = self.depth_range;
+        let (depth_near, depth_far) = if self.reversed_z {
+            (far_value, near_value)
+        } else {
+            (near_value, far_value)
+        };
+        RenderSettings {
+            oit: self.oit,
+            depth_near,
+            depth_far,
+            edge_antialiasing: self.edge_antialiasing,
+            depth_func: self.depth_func,
+            depth_bias_constant: self.depth_bias.0,
+            depth_bias_slope: self.depth_bias.1,
+            msaa_samples: self.msaa_samples,
+            sample_pattern: self.sample_pattern,
+            debug_mode: self.debug_mode,
+            pixel_perfect: self.pixel_perfect,
+            alpha_to_coverage: self.alpha_to_coverage,
+        }
+    }
+    /// The combined view and projection transform of the camera.
+    fn view_projection(&self) -> Mat4 {
+        let settings = self.render_settings();
+        self.camera.projection(
+            self.width as f64 / self.height as f64,
+            settings.depth_near,
+            settings.depth_far,
+        ) * self.camera.view_matrix()
+    }
+    /// Project 3D triangles onto the screen. Triangles with a point behind the camera are
+    /// skipped.
+    fn project_triangles(&self, triangles: impl IntoIterator<Item = LitTri>) -> Vec<Projected> {
+        let view_projection = self.view_projection();
+        triangles
+            .into_iter()
+            .filter_map(|lit| self.project_triangle(view_projection, lit))
+            .collect()
+    }
+    /// Split a 3D triangle until its edges are no longer than [`Scene::tessellation_threshold`]
+    /// on the screen, adding the pieces to `pieces`.
+    fn tessellate(&self, view_projection: Mat4, lit: LitTri, pieces: &mut Vec<LitTri>) {
+        /// How many times deep a triangle can be split, so at most 1024 pieces.
+        const MAX_DEPTH: usize = 10;
+
+        let textured = |lit: &LitTri| {
+            self.materials
+                .get(lit.triangle.material)
+                .is_some_and(|material| material.texture.is_some())
+        };
+        let Some(threshold) = self.tessellation_threshold else {
+            pieces.push(lit);
+            return;
+        };
+        if threshold.is_nan() || threshold <= 0.0 || textured(&lit) {
+            pieces.push(lit);
+            return;
+        }
+
+        let mut stack = vec![(lit, 0)];
+        while let Some((lit, depth)) = stack.pop() {
+            // triangles partly behind the camera are culled anyway
+            let Some(projected) = self.project_triangle(view_projection, lit) else {
+                pieces.push(lit);
+                continue;
+            };
+            let points = projected.triangle.points;
+            let length = |i: usize| {
+                let edge = points[(i + 1) % 3] - points[i];
+                edge.dot(edge).sqrt()
+            };
+            let longest = (0..3)
+                .max_by(|&a, &b| length(a).total_cmp(&length(b)))
+                .unwrap_or(0);
+            let longest_length = length(longest);
+            if depth >= MAX_DEPTH || longest_length.is_nan() || longest_length <= threshold {
+                pieces.push(lit);
+                continue;
+            }
+
+            // split the edge from corner a to corner b, keeping the winding
+            let (a, b, c) = (longest, (longest + 1) % 3, (longest + 2) % 3);
+            let corners = lit.triangle.points;
+            let middle = (corners[a] + corners[b]) * 0.5;
+            let middle_normal = (lit.normals[a] + lit.normals[b]) * 0.5;
+            let piece = |points: [Vec3; 3], normals: [Vec3; 3]| LitTri {
+                triangle: Tri3 {
+                    points,
+                    ..lit.triangle
+                },
+                normals,
+                ..lit
+            };
+            stack.push((
+                piece(
+                    [corners[a], middle, corners[c]],
+                    [lit.normals[a], middle_normal, lit.normals[c]],
+                ),
+                depth + 1,
+            ));
+            stack.push((
+                piece(
+                    [middle, corners[b], corners[c]],
+                    [middle_normal, lit.normals[b], lit.normals[c]],
+                ),
+                depth + 1,
+            ));
+        }
+    }
+    /// Project a 3D triangle onto the screen with the camera's `view_projection`, or `None` if
+    /// a point is behind the camera.
+    fn project_triangle(&self, view_projection: Mat4, lit: LitTri) -> Option<Projected> {
+        let (width, height) = (self.width as f64, self.height as f64);
+        let triangle = lit.triangle;
+        let mut points = [Vec2 { x: 0.0, y: 0.0 }; 3];
+        let mut depths = [0.0; 3];
+        for (i, point) in triangle.points.iter().enumerate() {
+            let [x, y, z, w] = view_projection.transform([point.x(), point.y(), point.z(), 1.0]);
+            if w <= 0.0 {
+                return None;
+            }
+            points[i] = Vec2 {
+                x: (x / w + 1.0) / 2.0 * width,
+                y: (1.0 - y / w) / 2.0 * height,
+            };
+            depths[i] = z / w;
+        }
+        // triangles with a material that doesn't exist are left as they are
+        let material = self
+            .materials
+            .get(triangle.material)
+            .copied()
+            .unwrap_or_default();
+        Some(Projected {
+            triangle: Tri2::flat(points, triangle.color * material.base_color),
+            depths,
+            positions: triangle.points,
+            normals: lit.normals,
+            specular: if material.shininess > 0.0 {
+                Specular {
+                    color: material.specular_color,
+                    shininess: material.shininess,
+                }
+            } else {
+                lit.specular
+            },
+            texture: material.texture,
+        })
+    }
+    /// The index (into [`Scene::triangles`]) of the topmost 2D triangle covering the point `p`,
+    /// i.e. the last one added, and its color there, or `None` if there isn't one. Points on
+    /// edges count as covered, and translucent triangles count like opaque ones.
+    pub fn hit_test(&self, p: Vec2) -> Option<(usize, Vec3)> {
+        self.index
+            .get_or_init(|| SceneIndex::new(&self.triangles))
+            .query(p)
+            .filter(|&i| self.triangles[i].contains(p, 0.0))
+            .max()
+            .map(|i| (i, self.triangles[i].color_at(p)))
+    }
+    /// The index (into [`Scene::triangles_3d`]) of the nearest 3D triangle covering the screen
+    /// point `p`, or `None` if there isn't one. Back faces are skipped with
+    /// [`Scene::backface_culling`], and so are triangles outside the depth range.
+    pub fn pick(&self, p: Vec2) -> Option<usize> {
+        let view_projection = self.view_projection();
+        let settings = self.render_settings();
+
+        let mut nearest: Option<(usize, f64)> = None;
+        for (i, &triangle) in self.triangles_3d.iter().enumerate() {
+            let lit = LitTri::new(triangle, self.front_face);
+            let Some(projected) = self.project_triangle(view_projection, lit) else {
+                continue;
+            };
+            if self.backface_culling && self.back_facing(projected.triangle) {
+                continue;
+            }
+            let Some(weights) = point_to_bary(projected.triangle.points, p) else {
+                continue;
+            };
+            if weights.iter().any(|&w| w < 0.0) {
+                continue;
+            }
+            let depth = (0..3).map(|i| projected.depths[i] * weights[i]).sum();
+            if !settings.in_depth_range(depth) {
+                continue;
+            }
+            if nearest.is_none_or(|(_, nearest_depth)| settings.nearer(depth, nearest_depth)) {
+                nearest = Some((i, depth));
+            }
+        }
+        nearest.map(|(i, _)| i)
+    }
+    /// The width of the output.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    /// The height of the output.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    /// The width and height of the output, in the order image libraries like egui take them.
+    pub fn size(&self) -> [usize; 2] {
+        [self.width, self.height]
+    }
+    /// The color of the pixel at (x, y).
+    pub fn pixel(&self, x: usize, y: usize) -> Vec3 {
+        self.output[y * self.width + x].to_vec3()
+    }
+    /// The pixels of the output as they're stored, row by row.
+    pub fn pixels(&self) -> &[P] {
+        &self.output
+    }
+    /// The depth of the pixel at (x, y).
+    pub fn depth(&self, x: usize, y: usize) -> f64 {
+        self.depth[y * self.width + x]
+    }
+    /// Clear the color buffer to [`Scene::clear_color`], or the background if there is one (see
+    /// [`Scene::set_background`]), leaving the depth buffer alone.
+    pub fn clear_color_buffer(&mut self) {
+        self.dirty = true;
+        if self.background.is_none() {
+            self.output.fill(P::from_vec3(self.clear_color));
+            return;
+        }
+        for (i, pixel) in self.output.iter_mut().enumerate() {
+            let (x, y) = (i % self.width, i / self.width);
+            (*pixel) = P::from_vec3(Self::background_color(
+                self.background.as_ref(),
+                self.clear_color,
+                x,
+                y,
+                self.width,
+                self.height,
+            ));
+        }
+    }
+    /// Clear the color buffer to `background` instead of [`Scene::clear_color`], or go back to
+    /// the clear color with `None`. Along each axis, a texture that clamps (see
+    /// [`Texture::address_u`]) is stretched over the whole output, and one that repeats or
+    /// mirrors is tiled with a texel per pixel. Triangles are drawn on top as usual.
+    pub fn set_background(&mut self, background: Option<Texture>) {
+        self.dirty = true;
+        self.background = background;
+    }
+    /// The image the color buffer is cleared to, if there is one.
+    pub fn background(&self) -> Option<&Texture> {
+        self.background.as_ref()
+    }
+    /// The color the pixel at (x, y) of a `width` by `height` buffer is cleared to: the
+    /// background there, or `clear_color` if there isn't one.
+    fn background_color(
+        background: Option<&Texture>,
+        clear_color: Vec3,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Vec3 {
+        let Some(background) = background else {
+            return clear_color;
+        };
+        /// The texture coordinate of the middle of `pixel`, stretched over `size` pixels, or
+        /// a texel per pixel if it's tiled.
+        fn coordinate(pixel: usize, size: usize, texels: usize, mode: AddressMode) -> f64 {
+            let size = match mode {
+                AddressMode::Clamp => size,
+                AddressMode::Repeat | AddressMode::Mirror => texels,
+            };
+            (pixel as f64 + 0.5) / size as f64
+        }
+        background.sample(Vec2 {
+            x: coordinate(x, width, background.width(), background.address_u),
+            y: coordinate(y, height, background.height(), background.address_v),
+        })
+    }
+    /// Clear the depth buffer to the farthest possible depth (infinity, or negative infinity with
+    /// a reversed depth range), leaving the color buffer alone.
+    pub fn clear_depth_buffer(&mut self) {
+        self.dirty = true;
+        let farthest = self.render_settings().farthest();
+        self.depth.fill(farthest);
+    }
+    /// Copy the pixels in the rectangle `src` (top left inclusive, bottom right exclusive) of the
+    /// color buffer so its top left is at `dst_top_left`. Coordinates are rounded down to whole
+    /// pixels, parts outside the output are clipped, and the regions may overlap.
+    pub fn blit(&mut self, src: (Vec2, Vec2), dst_top_left: Vec2) {
+        self.dirty = true;
+        let (width, height) = (self.width as i64, self.height as i64);
+        let (mut src_x, mut src_y) = (src.0.x.floor() as i64, src.0.y.floor() as i64);
+        let (src_end_x, src_end_y) = (
+            (src.1.x.floor() as i64).min(width),
+            (src.1.y.floor() as i64).min(height),
+        );
+        let (mut dst_x, mut dst_y) = (
+            dst_top_left.x.floor() as i64,
+            dst_top_left.y.floor() as i64,
+        );
+
+        // clip the source to the output, then the destination, moving the other to match
+        let offset_x = (-src_x).max(-dst_x).max(0);
+        let offset_y = (-src_y).max(-dst_y).max(0);
+        src_x += offset_x;
+        dst_x += offset_x;
+        src_y += offset_y;
+        dst_y += offset_y;
+        let copy_width = (src_end_x - src_x).min(width - dst_x);
+        let copy_height = (src_end_y - src_y).min(height - dst_y);
+        if copy_width <= 0 || copy_height <= 0 {
+            return;
+        }
+
+        let row = |y: i64, x: i64| (y * width + x) as usize;
+        let copy_row = |output: &mut Vec<P>, i: i64| {
+            let start = row(src_y + i, src_x);
+            output.copy_within(start..start + copy_width as usize, row(dst_y + i, dst_x));
+        };
+        // copy rows in the order that doesn't overwrite rows that are still to be copied
+        if dst_y > src_y {
+            (0..copy_height)
+                .rev()
+                .for_each(|i| copy_row(&mut self.output, i));
+        } else {
+            (0..copy_height).for_each(|i| copy_row(&mut self.output, i));
+        }
+    }
+    /// Replace the color buffer with the depth buffer as grayscale, with depths at `near` (or
+    /// nearer) black and depths at `far` (or farther, including cleared pixels) white.
+    pub fn depth_to_framebuffer(&mut self, near: f64, far: f64) {
+        self.dirty = true;
+        let range = far - near;
+        for (color, &depth) in self.output.iter_mut().zip(&self.depth) {
+            let value = if range == 0.0 {
+                if depth <= near { 0.0 } else { 1.0 }
+            } else {
+                ((depth - near) / range).clamp(0.0, 1.0)
+            };
+            (*color) = P::from_vec3(Vec3::new(value, value, value));
+        }
+    }
+    /// Render this Scene. The 3D triangles are drawn first with depth testing, then the 2D
+    /// triangles on top in order.
+    ///
+    /// Rendering draws on top of whatever is already in the color and depth buffers, so several
+    /// passes can be combined, e.g. drawing opaque geometry, calling
+    /// [`Scene::clear_depth_buffer`], then drawing an overlay. Clear the buffers with
+    /// [`Scene::clear_color_buffer`] and [`Scene::clear_depth_buffer`], or set
+    /// [`Scene::clear_on_render`] to clear both at the start of every render.
+    ///
+    /// If nothing that affects the output has changed since the last render, and the buffers
+    /// haven't been touched, the render is skipped and the output is left as it is, with
+    /// [`RenderStats::skipped`] set.
+    pub fn render(&mut self) {
+        let start = Instant::now();
+        let (frame, stats) = self.prepare_frame();
+        let key = (frame, self.clear_color, self.clear_on_render);
+        if !self.dirty && self.last_render.as_ref() == Some(&key) {
+            event!(Level::TRACE, "nothing changed since the last render, skipping");
+            self.stats.skipped = true;
+            return;
+        }
+
+        self.draw_frame(&key.0, stats, &FlatShader);
+        self.stats.render_time = start.elapsed();
+        self.last_render = Some(key);
+        self.dirty = false;
+    }
+    /// Render this Scene like [`Scene::render`], coloring every covered pixel with `shader`.
+    /// This always renders.
+    pub fn render_with_shader(&mut self, shader: &impl Shader) {
+        let start = Instant::now();
+        let (frame, stats) = self.prepare_frame();
+        self.draw_frame(&frame, stats, shader);
+        self.stats.render_time = start.elapsed();
+        self.dirty = true;
+    }
+    /// Render this Scene like [`Scene::render`], but stop drawing when `deadline` passes, for
+    /// when a late frame is worse than an unfinished one. Triangles are drawn biggest first, so
+    /// whatever's left out covers as little of the screen as possible. Returns whether every
+    /// triangle was drawn; the ones that weren't are counted in
+    /// [`RenderStats::triangles_over_deadline`].
+    ///
+    /// This always renders, without multisampling, and 2D triangles are drawn by size instead of
+    /// in order, so overlapping translucent ones can blend differently.
+    pub fn render_with_deadline(&mut self, deadline: Instant) -> bool {
+        let start = Instant::now();
+        let (frame, mut stats) = self.prepare_frame();
+        if self.clear_on_render {
+            self.clear_color_buffer();
+            self.clear_depth_buffer();
+        }
+        self.dirty = true;
+
+        // (area, whether it's 3D, index)
+        let mut order = frame
+            .projected
+            .iter()
+            .enumerate()
+            .map(|(i, projected)| (projected.triangle.signed_area().abs(), true, i))
+            .chain(
+                frame
+                    .triangles
+                    .iter()
+                    .enumerate()
+                    .map(|(i, triangle)| (triangle.signed_area().abs(), false, i)),
             )
-            .build()
-            .unwrap()
-            .into_canvas();
+            .collect::<Vec<(f64, bool, usize)>>();
+        order.sort_by(|a, b| b.0.total_cmp(&a.0));
 
-        let creator = canvas.texture_creator();
+        let mut single = Frame {
+            triangles: Vec::with_capacity(1),
+            projected: Vec::with_capacity(1),
+            ..frame
+        };
+        stats.pixels_shaded = 0;
+        let mut drawn = 0;
+        for &(_, is_3d, index) in &order {
+            if Instant::now() >= deadline {
+                break;
+            }
+            single.triangles.clear();
+            single.projected.clear();
+            if is_3d {
+                single.projected.push(frame.projected[index]);
+            } else {
+                single.triangles.push(frame.triangles[index]);
+            }
+            let mut band = Band::new(&mut self.output, &mut self.depth, self.width, 0);
+            band.draw(&single, &FlatShader);
+            stats.pixels_shaded += band.pixels_shaded;
+            drawn += 1;
+        }
 
-        let mut texture = creator
-            .create_texture_static(
-                sdl3::pixels::PixelFormat::try_from(sdl3::sys::pixels::SDL_PIXELFORMAT_RGB24)
-                    .unwrap(),
-                self.output[0].len() as u32,
-                self.output.len() as u32,
+        stats.triangles_over_deadline = order.len() - drawn;
+        stats.triangles_drawn = drawn;
+        stats.render_time = start.elapsed();
+        self.stats = stats;
+        if stats.triangles_over_deadline > 0 {
+            event!(
+                Level::DEBUG,
+                "render missed its deadline with {} triangles left",
+                stats.triangles_over_deadline
+            );
+        }
+        stats.triangles_over_deadline == 0
+    }
+    /// Draw a prepared frame into the output with `shader`, clearing it first if
+    /// [`Scene::clear_on_render`] is set.
+    fn draw_frame(&mut self, frame: &Frame, mut stats: RenderStats, shader: &impl Shader) {
+        let span = span!(Level::TRACE, "render_scene");
+        let _enter = span.enter();
+
+        if self.clear_on_render {
+            self.clear_color_buffer();
+            self.clear_depth_buffer();
+        }
+
+        if frame.settings.msaa_samples > 1 {
+            let mut band = Band::new(&mut self.output, &mut self.depth, self.width, 0);
+            band.draw_multisampled(frame, shader);
+            stats.pixels_shaded = band.pixels_shaded;
+        } else {
+            stats.edge_cache_hits = self.update_edge_cache(frame);
+            let mut band = Band::new(&mut self.output, &mut self.depth, self.width, 0);
+            if let Some((_, edges)) = &self.edge_cache {
+                band = band.with_edges(edges);
+            }
+            band.draw(frame, shader);
+            stats.pixels_shaded = band.pixels_shaded;
+        }
+        self.stats = stats;
+    }
+    /// Set up the edge equations of the frame's 2D triangles again, unless they're already
+    /// cached for the same triangles. Returns the number reused from the cache.
+    fn update_edge_cache(&mut self, frame: &Frame) -> usize {
+        let cached = !self.triangles_changed
+            && self.edge_cache.as_ref().is_some_and(|(key, edges)| {
+                *key == (self.origin, self.pixel_perfect) && edges.len() == frame.triangles.len()
+            });
+        if cached {
+            return frame.triangles.len();
+        }
+        event!(Level::TRACE, "setting up edges for {} triangles", frame.triangles.len());
+        self.edge_cache = Some((
+            (self.origin, self.pixel_perfect),
+            frame.triangles.iter().map(|&triangle| EdgeSetup::new(triangle)).collect(),
+        ));
+        self.triangles_changed = false;
+        0
+    }
+    /// Rasterize one 2D triangle straight into the output without clearing it or adding the
+    /// triangle to the Scene, for drawing geometry as it arrives. Drawing triangles one by one
+    /// gives the same output as rendering them together (except with order-independent
+    /// transparency, which only sorts out the triangles drawn together, and multisampling, which
+    /// averages each triangle's samples before the next one is drawn, so edges where triangles
+    /// meet can blend slightly differently). Like in [`Scene::render`], 2D triangles are drawn
+    /// over everything without depth testing.
+    pub fn draw_triangle(&mut self, tri: Tri2) {
+        self.dirty = true;
+        let tri = self.to_screen(tri);
+        if !tri.is_finite() {
+            event!(Level::WARN, "skipped non-finite triangle: {tri:?}");
+            return;
+        }
+        if self.off_screen(tri.bounding_box()) {
+            return;
+        }
+
+        let frame = Frame {
+            triangles: vec![tri],
+            projected: Vec::new(),
+            settings: self.render_settings(),
+            lighting: None,
+            textures: Vec::new(),
+        };
+        Band::new(&mut self.output, &mut self.depth, self.width, 0)
+            .draw_multisampled(&frame, &FlatShader);
+    }
+    /// Draw `mesh` straight into the output once for each of `transforms`, depth tested against
+    /// what's already there, like [`Scene::draw_triangle`] for 3D. The mesh's vertices are
+    /// transformed as they're drawn, so this doesn't copy the mesh for each instance.
+    pub fn draw_instanced(&mut self, mesh: &Mesh, transforms: &[Mat4]) {
+        self.dirty = true;
+        let view_projection = self.view_projection();
+        let mut frame = Frame {
+            triangles: Vec::new(),
+            projected: Vec::new(),
+            settings: self.render_settings(),
+            lighting: self.lighting(),
+            textures: self.textures.clone(),
+        };
+        let mut lit = Vec::with_capacity(mesh.triangle_count());
+        for &transform in transforms {
+            let normal_matrix = transform.normal_matrix();
+            lit.clear();
+            for triangle in mesh.lit_triangles(self.front_face) {
+                let triangle = LitTri {
+                    triangle: Tri3 {
+                        points: triangle.triangle.points.map(|point| transform.transform_point(point)),
+                        ..triangle.triangle
+                    },
+                    normals: triangle.normals.map(|normal| normal_matrix.transform(normal)),
+                    ..triangle
+                };
+                self.tessellate(view_projection, triangle, &mut lit);
+            }
+            frame.projected = self
+                .project_triangles(lit.iter().copied())
+                .into_iter()
+                .filter(|projected| self.should_draw(projected))
+                .collect();
+            Band::new(&mut self.output, &mut self.depth, self.width, 0)
+                .draw_multisampled(&frame, &FlatShader);
+        }
+    }
+    /// Clear the buffers and draw just the 2D triangle at `index` (see [`Scene::triangles`]),
+    /// for finding out what's wrong with it.
+    pub fn render_only(&mut self, index: usize) -> Result<(), Report<TriangleIndexError>> {
+        let Some(&tri) = self.triangles.get(index) else {
+            return Err(Report::new(TriangleIndexError).attach_printable(format!(
+                "index {index}, but there are {} triangles",
+                self.triangles.len()
+            )));
+        };
+        self.clear_color_buffer();
+        self.clear_depth_buffer();
+        self.draw_triangle(tri);
+        Ok(())
+    }
+    /// Draw a connected line through `points`, `width` pixels thick, straight into the output
+    /// like [`Scene::draw_triangle`]. Corners get miter joins, or bevel joins where the miter
+    /// would stick out more than 4 times the half-width (like SVG's default miter limit), and the
+    /// ends are cut off square at the first and last points. Edges are always antialiased, with
+    /// 16 samples per pixel.
+    pub fn draw_polyline(&mut self, points: &[Vec2], width: f64, color: Vec3) {
+        /// The most a miter can stick out, as a multiple of the half-width.
+        const MITER_LIMIT: f64 = 4.0;
+        /// The samples per pixel along each axis.
+        const SAMPLES: usize = 4;
+
+        self.dirty = true;
+        if width.is_nan() || width <= 0.0 || points.iter().any(|point| !point.is_finite()) {
+            event!(Level::WARN, "skipped polyline with width {width}: {points:?}");
+            return;
+        }
+        let half_width = width / 2.0;
+        let mut points = points
+            .iter()
+            .map(|&point| self.to_screen_point(point))
+            .collect::<Vec<Vec2>>();
+        points.dedup();
+
+        // the line is made of triangles, and a sample is covered if it's in any of them
+        let mut pieces = Vec::new();
+        for segment in points.windows(2) {
+            let [a, b] = [segment[0], segment[1]];
+            let normal = (b - a).normalize().clockwise90() * half_width;
+            pieces.push([a + normal, b + normal, b - normal]);
+            pieces.push([a + normal, b - normal, a - normal]);
+        }
+        for joint in points.windows(3) {
+            let [before, point, after] = [joint[0], joint[1], joint[2]];
+            let (into, out_of) = ((point - before).normalize(), (after - point).normalize());
+            // the gap to fill is on the outside of the turn
+            let side = if into.x * out_of.y - into.y * out_of.x >= 0.0 {
+                half_width
+            } else {
+                -half_width
+            };
+            let (normal_in, normal_out) = (into.clockwise90() * side, out_of.clockwise90() * side);
+            pieces.push([point, point + normal_in, point + normal_out]);
+
+            let bisector = (normal_in + normal_out).normalize();
+            let cos_half_angle = bisector.dot(normal_in) / half_width;
+            if cos_half_angle > 1.0 / MITER_LIMIT {
+                let miter = point + bisector * (half_width / cos_half_angle);
+                pieces.push([point + normal_in, miter, point + normal_out]);
+            }
+        }
+
+        // a bit per sample for each pixel in the bounding box of the line
+        let Some((top_left, bottom_right)) = pieces
+            .iter()
+            .map(|&points| Tri2::flat(points, color).bounding_box())
+            .reduce(|(a_min, a_max), (b_min, b_max)| {
+                (
+                    Vec2 { x: a_min.x.min(b_min.x), y: a_min.y.min(b_min.y) },
+                    Vec2 { x: a_max.x.max(b_max.x), y: a_max.y.max(b_max.y) },
+                )
+            })
+        else {
+            return;
+        };
+        let clip = |corner: Vec2| {
+            let corner = corner.floor_to_ivec2();
+            (
+                corner.x.clamp(0, self.width as i32) as usize,
+                corner.y.clamp(0, self.height as i32) as usize,
             )
-            .unwrap();
+        };
+        let (x0, y0) = clip(top_left - Vec2 { x: 1.0, y: 1.0 });
+        let (x1, y1) = clip(bottom_right + Vec2 { x: 2.0, y: 2.0 });
+        let box_width = x1 - x0;
+        let mut masks = vec![0u16; box_width * (y1 - y0)];
+        for piece in pieces {
+            let triangle = Tri2::flat(piece, color);
+            let (piece_top_left, piece_bottom_right) = triangle.bounding_box();
+            let (px0, py0) = clip(piece_top_left - Vec2 { x: 1.0, y: 1.0 });
+            let (px1, py1) = clip(piece_bottom_right + Vec2 { x: 2.0, y: 2.0 });
+            for y in py0..py1 {
+                for x in px0..px1 {
+                    let mask = &mut masks[(y - y0) * box_width + (x - x0)];
+                    for i in 0..SAMPLES * SAMPLES {
+                        let sample = Vec2 {
+                            x: x as f64 + ((i % SAMPLES) as f64 + 0.5) / SAMPLES as f64 - 0.5,
+                            y: y as f64 + ((i / SAMPLES) as f64 + 0.5) / SAMPLES as f64 - 0.5,
+                        };
+                        if triangle.inside(sample) {
+                            (*mask) |= 1 << i;
+                        }
+                    }
+                }
+            }
+        }
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let mask = masks[(y - y0) * box_width + (x - x0)];
+                if mask == 0 {
+                    continue;
+                }
+                let coverage = mask.count_ones() as f64 / (SAMPLES * SAMPLES) as f64;
+                let pixel = &mut self.output[y * self.width + x];
+                (*pixel) = P::from_vec3(color * coverage + pixel.to_vec3() * (1.0 - coverage));
+            }
+        }
+    }
+    /// Fill the pixels within `radius` of `center` with `color`, straight into the output like
+    /// [`Scene::draw_triangle`]. Parts off the output are skipped.
+    pub fn fill_circle(&mut self, center: Vec2, radius: f64, color: Vec3) {
+        self.dirty = true;
+        if radius.is_nan() || radius < 0.0 || !center.is_finite() {
+            event!(Level::WARN, "skipped circle at {center:?} with radius {radius}");
+            return;
+        }
+        let center = self.to_screen_point(center);
+        let offset = Vec2 {
+            x: radius,
+            y: radius,
+        };
+        self.fill_pixels(center - offset, center + offset, color, |pixel| {
+            let difference = pixel - center;
+            difference.dot(difference) <= radius * radius
+        });
+    }
+    /// Fill the pixels from `min` up to (but not including) `max` with `color`, straight into
+    /// the output like [`Scene::draw_triangle`], so rectangles that share an edge don't overlap.
+    /// Parts off the output are skipped.
+    pub fn fill_rect(&mut self, min: Vec2, max: Vec2, color: Vec3) {
+        self.dirty = true;
+        if !min.is_finite() || !max.is_finite() {
+            event!(Level::WARN, "skipped rectangle from {min:?} to {max:?}");
+            return;
+        }
+        // with the origin at the bottom the corners swap over
+        let (a, b) = (self.to_screen_point(min), self.to_screen_point(max));
+        let top_left = Vec2 {
+            x: a.x.min(b.x),
+            y: a.y.min(b.y),
+        };
+        let bottom_right = Vec2 {
+            x: a.x.max(b.x),
+            y: a.y.max(b.y),
+        };
+        // pixels are tested in the origin's coordinates, so the row at `max` is the one left out
+        // either way
+        let (origin, height) = (self.origin, self.height as f64);
+        self.fill_pixels(top_left, bottom_right, color, |pixel| {
+            let y = match origin {
+                Origin::TopLeft => pixel.y,
+                Origin::BottomLeft => (height - 1.0) - pixel.y,
+            };
+            pixel.x >= min.x && y >= min.y && pixel.x < max.x && y < max.y
+        });
+    }
+    /// Set the pixels from `top_left` to `bottom_right` (in the output's coordinates) that are
+    /// `inside` to `color`.
+    fn fill_pixels(
+        &mut self,
+        top_left: Vec2,
+        bottom_right: Vec2,
+        color: Vec3,
+        inside: impl Fn(Vec2) -> bool,
+    ) {
+        let x0 = top_left.x.ceil().max(0.0) as usize;
+        let y0 = top_left.y.ceil().max(0.0) as usize;
+        let x1 = (bottom_right.x.floor() + 1.0).clamp(0.0, self.width as f64) as usize;
+        let y1 = (bottom_right.y.floor() + 1.0).clamp(0.0, self.height as f64) as usize;
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let pixel = Vec2 {
+                    x: x as f64,
+                    y: y as f64,
+                };
+                if inside(pixel) {
+                    self.output[y * self.width + x] = P::from_vec3(color);
+                }
+            }
+        }
+    }
+    /// How much of each pixel is covered by the (2D and 3D) triangles, row by row: 1.0 where
+    /// covered and 0.0 for the background, antialiased if [`Scene::edge_antialiasing`] is on.
+    /// Colors, transparency, and the buffers are ignored.
+    pub fn coverage_mask(&self) -> Vec<f64> {
+        let (frame, _) = self.prepare_frame();
+        raster::coverage_mask(&frame, self.width, self.height)
+    }
+    /// Draw an outline `thickness` pixels thick in `color` around everything the triangles cover
+    /// (see [`Scene::coverage_mask`]), for highlighting. Pixels that aren't covered but are
+    /// within `thickness` of a covered one are colored, so the outline has round corners.
+    pub fn outline(&mut self, thickness: usize, color: Vec3) {
+        self.dirty = true;
+        let covered = self
+            .coverage_mask()
+            .into_iter()
+            .map(|coverage| coverage > 0.0)
+            .collect::<Vec<bool>>();
+        let (width, height) = (self.width as isize, self.height as isize);
+        let is_covered = |x: isize, y: isize| {
+            x >= 0 && y >= 0 && x < width && y < height && covered[(y * width + x) as usize]
+        };
 
-        self.display_tex_sdl3(&mut texture);
+        // only covered pixels on the edge can be near uncovered ones
+        let radius = thickness as isize;
+        let mut ring = vec![false; covered.len()];
+        for (y, x) in (0..height).flat_map(|y| (0..width).map(move |x| (y, x))) {
+            let on_edge = is_covered(x, y)
+                && [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                    .iter()
+                    .any(|&(dx, dy)| !is_covered(x + dx, y + dy));
+            if !on_edge {
+                continue;
+            }
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    let (ox, oy) = (x + dx, y + dy);
+                    if dx * dx + dy * dy <= radius * radius
+                        && ox >= 0
+                        && oy >= 0
+                        && ox < width
+                        && oy < height
+                        && !is_covered(ox, oy)
+                    {
+                        ring[(oy * width + ox) as usize] = true;
+                    }
+                }
+            }
+        }
 
-        canvas.copy(&texture, None, None)?;
+        for (pixel, in_ring) in self.output.iter_mut().zip(ring) {
+            if in_ring {
+                (*pixel) = P::from_vec3(color);
+            }
+        }
+    }
+    /// Render this Scene over `passes` passes, calling `on_pass` after each one so the output
+    /// can be shown while it's refined. Each pass but the last only renders every 2nd, 4th, 8th
+    /// and so on pixel across and down (starting with the coarsest), filling in the pixels in
+    /// between with copies, so the first one is quick. The previews aren't multisampled, but the
+    /// last pass is a normal [`Scene::render`], so the output ends up the same as without them.
+    pub fn render_progressive(&mut self, passes: usize, mut on_pass: impl FnMut(&Scene<P>)) {
+        let span = span!(Level::TRACE, "render_progressive", passes);
+        let _enter = span.enter();
 
-        canvas.present();
+        let (frame, _) = self.prepare_frame();
+        let farthest = frame.settings.farthest();
+        // the last pass starts from the buffers as they were
+        let original = (!self.clear_on_render).then(|| (self.output.clone(), self.depth.clone()));
+        for pass in 0..passes.saturating_sub(1) {
+            let step = 1usize << (passes - 1 - pass).min(usize::BITS as usize - 1);
+            let (width, height) = (self.width.div_ceil(step), self.height.div_ceil(step));
+            // each pixel of the smaller output is the top-left pixel of a step by step block
+            let mut colors = Vec::with_capacity(width * height);
+            let mut depths = Vec::with_capacity(width * height);
+            for (y, x) in (0..height).flat_map(|y| (0..width).map(move |x| (y, x))) {
+                if self.clear_on_render {
+                    colors.push(P::from_vec3(Self::background_color(
+                        self.background.as_ref(),
+                        self.clear_color,
+                        x * step,
+                        y * step,
+                        self.width,
+                        self.height,
+                    )));
+                    depths.push(farthest);
+                } else {
+                    let index = y * step * self.width + x * step;
+                    colors.push(self.output[index]);
+                    depths.push(self.depth[index]);
+                }
+            }
+            Band::new(&mut colors, &mut depths, width, 0)
+                .draw(&frame.scale(1.0 / step as f64), &FlatShader);
 
-        let mut pump = sdl.event_pump()?;
-        for event in pump.wait_iter() {
-            match event {
-                sdl3::event::Event::Quit { timestamp: _ } => {
-                    break;
+            for (y, row) in self.output.chunks_mut(self.width).enumerate() {
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    (*pixel) = colors[(y / step) * width + x / step];
                 }
-                sdl3::event::Event::KeyDown {
-                    timestamp: _,
-                    window_id: _,
-                    keycode,
-                    scancode: _,
-                    keymod: _,
-                    repeat: _,
-                    which: _,
-                    raw: _,
-                } => match keycode.unwrap() {
-                    sdl3::keyboard::Keycode::Q => {
-                        break;
+            }
+            self.dirty = true;
+            on_pass(self);
+        }
+
+        if let Some((output, depth)) = original {
+            self.output = output;
+            self.depth = depth;
+        }
+        self.dirty = true;
+        self.render();
+        on_pass(self);
+    }
+    /// Render `samples` sub-frames and average them into the output, for motion blur. Before
+    /// each sub-frame the buffers are cleared and `update` is called with the sub-frame time,
+    /// from 0.0 to 1.0, to move the geometry.
+    pub fn render_motion_blur(
+        &mut self,
+        samples: usize,
+        mut update: impl FnMut(&mut Scene<P>, f64),
+    ) {
+        let span = span!(Level::TRACE, "render_motion_blur", samples);
+        let _enter = span.enter();
+
+        let samples = samples.max(1);
+        let mut accumulated = vec![Vec3::new(0.0, 0.0, 0.0); self.output.len()];
+        for i in 0..samples {
+            let time = if samples == 1 {
+                0.0
+            } else {
+                i as f64 / (samples - 1) as f64
+            };
+            self.clear_color_buffer();
+            self.clear_depth_buffer();
+            update(self, time);
+            self.render();
+
+            for (sum, color) in accumulated.iter_mut().zip(&self.output) {
+                (*sum) += color.to_vec3();
+            }
+        }
+
+        for (color, sum) in self.output.iter_mut().zip(accumulated) {
+            (*color) = P::from_vec3(sum * (1.0 / samples as f64));
+        }
+        self.dirty = true;
+    }
+    /// The counters from the last render.
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+    /// Move a 2D point from [`Scene::origin`]'s coordinates to the output's, where Y goes down.
+    fn to_screen_point(&self, point: Vec2) -> Vec2 {
+        match self.origin {
+            Origin::TopLeft => point,
+            Origin::BottomLeft => Vec2 {
+                x: point.x,
+                y: (self.height as f64 - 1.0) - point.y,
+            },
+        }
+    }
+    /// Move a 2D triangle from [`Scene::origin`]'s coordinates to the output's, rounding it to
+    /// whole pixels if [`Scene::pixel_perfect`] is set.
+    fn to_screen(&self, triangle: Tri2) -> Tri2 {
+        Tri2 {
+            points: triangle.points.map(|point| {
+                let point = self.to_screen_point(point);
+                if self.pixel_perfect {
+                    Vec2 {
+                        x: point.x.round(),
+                        y: point.y.round(),
                     }
-                    sdl3::keyboard::Keycode::R => {
-                        self.render();
-                        self.display_tex_sdl3(&mut texture);
+                } else {
+                    point
+                }
+            }),
+            ..triangle
+        }
+    }
+    /// Is the bounding box entirely outside the output?
+    fn off_screen(&self, (top_left, bottom_right): (Vec2, Vec2)) -> bool {
+        bottom_right.x < 0.0
+            || bottom_right.y < 0.0
+            || top_left.x >= self.width as f64
+            || top_left.y >= self.height as f64
+    }
+    /// Does a projected 3D triangle face away from the camera, according to
+    /// [`Scene::front_face`]?
+    fn back_facing(&self, triangle: Tri2) -> bool {
+        // the signed area is negative for points that look counter-clockwise on the screen
+        match self.front_face {
+            FrontFace::Ccw => triangle.signed_area() > 0.0,
+            FrontFace::Cw => triangle.signed_area() < 0.0,
+        }
+    }
+    /// Project and cull the triangles for rendering, counting what was drawn and culled.
+    /// Triangles with non-finite coordinates are skipped and counted as culled, as are back faces
+    /// with [`Scene::backface_culling`].
+    fn prepare_frame(&self) -> (Frame, RenderStats) {
+        let meshes = self
+            .meshes
+            .iter()
+            .map(|mesh| self.select_lod(mesh))
+            .collect::<Vec<&Mesh>>();
+        let view_projection = self.view_projection();
+        let mut lit = Vec::with_capacity(
+            self.triangles_3d.len() + meshes.iter().map(|mesh| mesh.triangle_count()).sum::<usize>(),
+        );
+        for triangle in self
+            .triangles_3d
+            .iter()
+            .map(|&triangle| LitTri::new(triangle, self.front_face))
+            .chain(
+                meshes
+                    .iter()
+                    .flat_map(|mesh| mesh.lit_triangles(self.front_face)),
+            )
+        {
+            self.tessellate(view_projection, triangle, &mut lit);
+        }
+        let projected_count = lit.len();
+        let projected = self
+            .project_triangles(lit)
+            .into_iter()
+            .filter(|projected| self.should_draw(projected))
+            .collect::<Vec<Projected>>();
+        let triangles = self
+            .triangles
+            .iter()
+            .filter(|triangle| {
+                if !triangle.is_finite() {
+                    event!(Level::WARN, "skipped non-finite triangle: {triangle:?}");
+                    return false;
+                }
+                true
+            })
+            .map(|&triangle| self.to_screen(triangle))
+            .filter(|triangle| !self.off_screen(triangle.bounding_box()))
+            .collect::<Vec<Tri2>>();
+
+        let drawn = triangles.len() + projected.len();
+        let stats = RenderStats {
+            triangles_drawn: drawn,
+            triangles_culled: self.triangles.len() + projected_count - drawn,
+            pixels_shaded: 0,
+            skipped: false,
+            edge_cache_hits: 0,
+            render_time: Duration::ZERO,
+            triangles_over_deadline: 0,
+        };
+        event!(Level::TRACE, "prepared frame: {stats:?}");
+
+        let frame = Frame {
+            triangles,
+            projected,
+            settings: self.render_settings(),
+            lighting: self.lighting(),
+            textures: self.textures.clone(),
+        };
+        (frame, stats)
+    }
+    /// Whether a projected 3D triangle should be drawn: it's finite, not culled as a back face,
+    /// and on the screen.
+    fn should_draw(&self, projected: &Projected) -> bool {
+        if !projected.triangle.is_finite() || !projected.depths.iter().all(|d| d.is_finite()) {
+            event!(Level::WARN, "skipped non-finite 3D triangle: {projected:?}");
+            return false;
+        }
+        let back_face = self.backface_culling && self.back_facing(projected.triangle);
+        !back_face && !self.off_screen(projected.triangle.bounding_box())
+    }
+    /// The lights copied out for rendering, or `None` if there aren't any.
+    fn lighting(&self) -> Option<Lighting> {
+        (!self.lights.is_empty()).then(|| Lighting {
+            lights: self.lights.clone(),
+            ambient: self.ambient_light,
+            camera_position: self.camera.position,
+        })
+    }
+    /// A quick upper bound on the number of pixels the next render will shade
+    /// ([`RenderStats::pixels_shaded`]): the area of each triangle's bounding box on the output,
+    /// plus a pixel around it for antialiased edges, added up.
+    pub fn estimated_fragment_count(&self) -> u64 {
+        let (frame, _) = self.prepare_frame();
+        frame
+            .projected
+            .iter()
+            .map(|projected| projected.triangle)
+            .chain(frame.triangles.iter().copied())
+            .map(|triangle| {
+                let (top_left, bottom_right) = triangle.bounding_box();
+                let (xs, ys) = raster::clip_to_band(
+                    (top_left - Vec2 { x: 1.0, y: 1.0 }).floor_to_ivec2(),
+                    (bottom_right + Vec2 { x: 1.0, y: 1.0 }).floor_to_ivec2(),
+                    self.width,
+                    0,
+                    self.height,
+                );
+                (xs.len() * ys.len()) as u64
+            })
+            .sum()
+    }
+    /// Count the pixels of the output in each of `bins` equal ranges of luminance from 0.0 to
+    /// 1.0. Luminances outside that range go in the first or last bin, and NaN in the first.
+    pub fn luminance_histogram(&self, bins: usize) -> Vec<usize> {
+        let mut histogram = vec![0; bins];
+        if bins == 0 {
+            return histogram;
+        }
+        for color in &self.output {
+            let luminance = color.to_vec3().luminance().clamp(0.0, 1.0);
+            let bin = ((luminance * bins as f64) as usize).min(bins - 1);
+            histogram[bin] += 1;
+        }
+        histogram
+    }
+    /// Scale the output uniformly so that the luminance at `percentile` (from 0 to 100) maps to
+    /// 1.0. Does nothing if there are no pixels, or that luminance is zero.
+    pub fn auto_exposure(&mut self, percentile: f64) {
+        self.dirty = true;
+        if self.output.is_empty() {
+            return;
+        }
+        let mut luminances = self
+            .output
+            .iter()
+            .map(|color| color.to_vec3().luminance())
+            .collect::<Vec<f64>>();
+        let index = ((percentile.clamp(0.0, 100.0) / 100.0) * (luminances.len() - 1) as f64)
+            .round() as usize;
+        let (_, &mut luminance, _) = luminances.select_nth_unstable_by(index, f64::total_cmp);
+        event!(Level::TRACE, "luminance at {percentile}th percentile: {luminance}");
 
-                        canvas.copy(&texture, None, None)?;
-                        canvas.present();
+        if luminance <= 0.0 || !luminance.is_finite() {
+            return;
+        }
+        for color in &mut self.output {
+            (*color) = P::from_vec3(color.to_vec3() * (1.0 / luminance));
+        }
+    }
+    /// Darken creases and corners with screen-space ambient occlusion, using the depth buffer
+    /// from the last render. For pairs of points on opposite sides of each pixel, up to
+    /// `radius` pixels away, the pixel is occluded if their average depth is nearer than its
+    /// own, which is the case in concavities but not on flat (even slanted) surfaces. Colors are
+    /// multiplied by 1 - `strength` * the fraction of occluded pairs. Cleared pixels are left
+    /// alone.
+    pub fn apply_ssao(&mut self, radius: f64, strength: f64) {
+        /// The number of directions sampled (over half a circle, as each makes a pair).
+        const DIRECTIONS: usize = 8;
+        /// The number of distances sampled in each direction.
+        const STEPS: usize = 3;
+
+        self.dirty = true;
+        let settings = self.render_settings();
+        let epsilon = 1e-5 * (settings.depth_far - settings.depth_near).abs();
+        let offsets = (0..DIRECTIONS)
+            .flat_map(|direction| {
+                let angle = std::f64::consts::PI * direction as f64 / DIRECTIONS as f64;
+                (1..=STEPS).map(move |step| {
+                    let distance = radius * step as f64 / STEPS as f64;
+                    (
+                        (angle.cos() * distance).round() as isize,
+                        (angle.sin() * distance).round() as isize,
+                    )
+                })
+            })
+            .filter(|&offset| offset != (0, 0))
+            .collect::<Vec<(isize, isize)>>();
+
+        let (width, height) = (self.width as isize, self.height as isize);
+        let depth_at = |x: isize, y: isize| {
+            (x >= 0 && y >= 0 && x < width && y < height)
+                .then(|| self.depth[(y * width + x) as usize])
+                .filter(|depth| depth.is_finite())
+        };
+        let factors = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let Some(depth) = depth_at(x, y) else {
+                    return 1.0;
+                };
+                let (mut occluded, mut total) = (0, 0);
+                for &(dx, dy) in &offsets {
+                    let (Some(a), Some(b)) = (depth_at(x + dx, y + dy), depth_at(x - dx, y - dy))
+                    else {
+                        continue;
+                    };
+                    total += 1;
+                    let average = (a + b) / 2.0;
+                    if settings.nearer(average, depth) && (average - depth).abs() > epsilon {
+                        occluded += 1;
                     }
-                    sdl3::keyboard::Keycode::T => {
-                        (*self) = Self::new();
-                        self.render();
-                        self.display_tex_sdl3(&mut texture);
+                }
+                if total == 0 {
+                    1.0
+                } else {
+                    (1.0 - strength * occluded as f64 / total as f64).clamp(0.0, 1.0)
+                }
+            })
+            .collect::<Vec<f64>>();
+
+        for (color, factor) in self.output.iter_mut().zip(factors) {
+            (*color) = P::from_vec3(color.to_vec3() * factor);
+        }
+    }
+    /// Replace every pixel of the output with the nearest color in `palette`. With `dither`, the
+    /// difference is spread to the neighboring pixels (Floyd–Steinberg), so gradients become a
+    /// mix of palette colors instead of bands. Does nothing if the palette is empty.
+    pub fn quantize(&mut self, palette: &[Vec3], dither: bool) {
+        self.dirty = true;
+        if palette.is_empty() {
+            return;
+        }
+        let nearest = |color: Vec3| {
+            palette
+                .iter()
+                .copied()
+                .min_by(|&a, &b| {
+                    let (a, b) = (a - color, b - color);
+                    a.dot(a).total_cmp(&b.dot(b))
+                })
+                .unwrap()
+        };
+
+        // the error is diffused at full precision, whatever the pixels are stored as
+        let mut colors = self
+            .output
+            .iter()
+            .map(|color| color.to_vec3())
+            .collect::<Vec<Vec3>>();
+        let (width, height) = (self.width, self.height);
+        for y in 0..height {
+            for x in 0..width {
+                let old = colors[y * width + x];
+                let new = nearest(old);
+                self.output[y * width + x] = P::from_vec3(new);
+                if !dither {
+                    continue;
+                }
 
-                        canvas.copy(&texture, None, None)?;
-                        canvas.present();
+                let error = old - new;
+                let mut diffuse = |dx: isize, dy: usize, weight: f64| {
+                    let nx = x as isize + dx;
+                    if nx >= 0 && (nx as usize) < width && y + dy < height {
+                        colors[(y + dy) * width + nx as usize] += error * (weight / 16.0);
                     }
-                    _ => {}
-                },
-                _ => {}
+                };
+                diffuse(1, 0, 7.0);
+                diffuse(-1, 1, 3.0);
+                diffuse(0, 1, 5.0);
+                diffuse(1, 1, 1.0);
             }
         }
-
-        Ok::<_, sdl3::Error>(())
+    }
+    /// Turn weighted blended order-independent transparency on or off.
+    ///
+    /// When on, opaque triangles are drawn first, then translucent ones are accumulated with a
+    /// weight depending on their alpha and depth and resolved over the result, so the output
+    /// doesn't depend on the order translucent triangles were added in. When off, translucent
+    /// triangles are alpha blended in order.
+    pub fn set_oit(&mut self, oit: bool) {
+        self.oit = oit;
+    }
+    /// Turn on multisample antialiasing with `samples` samples per pixel, or turn it off with 1
+    /// (or 0). It applies to every way of rendering and drawing except
+    /// [`Scene::render_with_deadline`] and the previews of [`Scene::render_progressive`]. Each sample is rasterized
+    /// with its own color and depth and the pixel gets the average of their colors, so edges
+    /// are smooth, including where triangles cut through each other. The depth buffer keeps the
+    /// nearest depth of each pixel's samples. Rendering takes about `samples` times as long.
+    pub fn set_msaa(&mut self, samples: usize) {
+        self.msaa_samples = samples.max(1);
+    }
+    /// Set where the samples are in each pixel for multisample antialiasing
+    /// ([`SamplePattern::Grid`] by default).
+    pub fn set_sample_pattern(&mut self, pattern: SamplePattern) {
+        self.sample_pattern = pattern;
+    }
+    /// Render this Scene on `threads` threads, each one owning a horizontal band of the output.
+    ///
+    /// The bands don't overlap, so no synchronization is needed and the result is identical to
+    /// [`Scene::render`].
+    pub fn render_threaded(&mut self, threads: usize) {
+        let start = Instant::now();
+        self.dirty = true;
+        let span = span!(Level::TRACE, "render_scene_threaded", threads);
+        let _enter = span.enter();
+
+        if self.clear_on_render {
+            self.clear_color_buffer();
+            self.clear_depth_buffer();
+        }
+
+        let band_height = self.height.div_ceil(threads.max(1)).max(1);
+        let band_size = (band_height * self.width).max(1);
+        let (frame, mut stats) = self.prepare_frame();
+        // multisampling moves the triangles for each sample, so it can't use the edges
+        if frame.settings.msaa_samples <= 1 {
+            stats.edge_cache_hits = self.update_edge_cache(&frame);
+        }
+        let edges = self
+            .edge_cache
+            .as_ref()
+            .map(|(_, edges)| edges.as_slice())
+            .filter(|_| frame.settings.msaa_samples <= 1);
+        let frame = &frame;
+        let width = self.width;
+
+        stats.pixels_shaded = std::thread::scope(|scope| {
+            self.output
+                .chunks_mut(band_size)
+                .zip(self.depth.chunks_mut(band_size))
+                .enumerate()
+                .map(|(i, (colors, depths))| {
+                    scope.spawn(move || {
+                        let mut band = Band::new(colors, depths, width, i * band_height);
+                        if let Some(edges) = edges {
+                            band = band.with_edges(edges);
+                        }
+                        band.draw_multisampled(frame, &FlatShader);
+                        band.pixels_shaded
+                    })
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .sum()
+        });
+        stats.render_time = start.elapsed();
+        self.stats = stats;
+    }
+    /// Display the texture on the window.
+    #[cfg(feature = "sdl3")]
+    fn display_tex_sdl3(&self, texture: &mut sdl3::render::Texture, format: PixelFormat) {
+        let bytes_per_pixel = format.bytes_per_pixel();
+        let colors = self.output.iter().map(|val| val.to_vec3()).collect::<Vec<Vec3>>();
+        texture
+            .update(
+                None,
+                &rgb8_bytes(&colors)
+                    .chunks_exact(3)
+                    .flat_map(|rgb| {
+                        format
+                            .pack_rgb8([rgb[0], rgb[1], rgb[2]])
+                            .into_iter()
+                            .take(bytes_per_pixel)
+                    })
+                    .collect::<Vec<u8>>(),
+                self.width * bytes_per_pixel,
+            )
+            .unwrap();
+    }
+    /// Save a screenshot of the output to a timestamped PNG in the current directory. Does nothing
+    /// (except warn) without the `image_types` feature.
+    #[cfg(feature = "sdl3")]
+    fn save_screenshot(&self) {
+        #[cfg(feature = "image_types")]
+        {
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis();
+            let path = format!("screenshot-{timestamp}.png");
+            match self.save_png(&path) {
+                Ok(()) => event!(Level::INFO, "saved screenshot to {path}"),
+                Err(err) => event!(Level::ERROR, "failed to save screenshot: {err:?}"),
+            }
+        }
+        #[cfg(not(feature = "image_types"))]
+        event!(
+            Level::WARN,
+            "screenshots need the `image_types` feature, ignoring"
+        );
+    }
+    /// Clear the canvas to `clear_color`, copy the texture to it, draw a crosshair at the mouse
+    /// position (if it's in the window), and present it.
+    #[cfg(feature = "sdl3")]
+    fn present_sdl3(
+        canvas: &mut sdl3::render::WindowCanvas,
+        texture: &sdl3::render::Texture,
+        clear_color: Vec3,
+        mouse: Option<(f32, f32)>,
+    ) -> Result<(), sdl3::Error> {
+        let [r, g, b] = clear_color.to_rgb8();
+        canvas.set_draw_color(sdl3::pixels::Color::RGB(r, g, b));
+        canvas.clear();
+        canvas.copy(texture, None, None)?;
+
+        if let Some((x, y)) = mouse {
+            canvas.set_draw_color(sdl3::pixels::Color::RGB(255, 255, 255));
+            canvas.draw_line((x - CROSSHAIR_SIZE, y), (x + CROSSHAIR_SIZE, y))?;
+            canvas.draw_line((x, y - CROSSHAIR_SIZE), (x, y + CROSSHAIR_SIZE))?;
+        }
+
+        canvas.present();
+        Ok(())
+    }
+    /// Display this rendered Scene, with the default key bindings (see
+    /// [`Scene::default_sdl3_handler`]).
+    ///
+    /// A crosshair follows the mouse. It's only drawn on the window, so it never ends up in the
+    /// output or in screenshots.
+    #[cfg(feature = "sdl3")]
+    pub fn display_sdl3(&mut self) -> Result<(), impl std::error::Error> {
+        self.display_sdl3_with_handler(Self::default_sdl3_handler)
+    }
+    /// The key bindings used by [`Scene::display_sdl3`]:
+    /// - `Q` quits.
+    /// - `R` renders again.
+    /// - `T` makes a new random scene and renders it.
+    /// - `S` saves a screenshot (see [`Scene::save_png`]) to `screenshot-<unix millis>.png`.
+    ///
+    /// Call it from your own handler to keep these bindings.
+    #[cfg(feature = "sdl3")]
+    pub fn default_sdl3_handler(event: &sdl3::event::Event, scene: &mut Self) -> ControlFlow {
+        let sdl3::event::Event::KeyDown {
+            keycode: Some(keycode),
+            ..
+        } = event
+        else {
+            return ControlFlow::Continue;
+        };
+        match *keycode {
+            sdl3::keyboard::Keycode::Q => ControlFlow::Quit,
+            sdl3::keyboard::Keycode::R => {
+                scene.render();
+                if scene.stats().skipped {
+                    ControlFlow::Continue
+                } else {
+                    ControlFlow::Redraw
+                }
+            }
+            sdl3::keyboard::Keycode::S => {
+                scene.save_screenshot();
+                ControlFlow::Continue
+            }
+            sdl3::keyboard::Keycode::T => {
+                let random = Scene::new();
+                (*scene) = Self {
+                    triangles: random.triangles,
+                    ..Self::sized(random.width, random.height)
+                };
+                scene.render();
+                ControlFlow::Redraw
+            }
+            _ => ControlFlow::Continue,
+        }
+    }
+    /// Display this rendered Scene, passing every event to `handler` along with the Scene.
+    ///
+    /// The handler can change the Scene however it likes (rendering it, for example), but the
+    /// window only shows the new output once it returns [`ControlFlow::Redraw`]. Returning
+    /// [`ControlFlow::Quit`] closes the window. The window also closes on a quit event (after the
+    /// handler has seen it), and the crosshair and window events are handled here, so the
+    /// handler only needs to care about its own input. Don't change the Scene's size, as the
+    /// window keeps the size it opened with.
+    #[cfg(feature = "sdl3")]
+    pub fn display_sdl3_with_handler(
+        &mut self,
+        mut handler: impl FnMut(&sdl3::event::Event, &mut Self) -> ControlFlow,
+    ) -> Result<(), impl std::error::Error> {
+        let sdl = sdl3::init()?;
+
+        let mut canvas = sdl
+            .video()?
+            .window(
+                "ThreeD Window",
+                self.width as u32,
+                self.height as u32,
+            )
+            .build()
+            .unwrap()
+            .into_canvas();
+
+        let creator = canvas.texture_creator();
+        let pixel_format = self.pixel_format;
+        let clear_color = self.window_clear_color;
+
+        let mut texture = creator
+            .create_texture_static(
+                pixel_format.to_sdl3(),
+                self.width as u32,
+                self.height as u32,
+            )
+            .unwrap();
+
+        self.display_tex_sdl3(&mut texture, pixel_format);
+
+        let mut mouse = None;
+        Self::present_sdl3(&mut canvas, &texture, clear_color, mouse)?;
+
+        let mut pump = sdl.event_pump()?;
+        for event in pump.wait_iter() {
+            match handler(&event, self) {
+                ControlFlow::Continue => {}
+                ControlFlow::Redraw => {
+                    self.display_tex_sdl3(&mut texture, pixel_format);
+                    Self::present_sdl3(&mut canvas, &texture, clear_color, mouse)?;
+                }
+                ControlFlow::Quit => break,
+            }
+            match event {
+                sdl3::event::Event::Quit { timestamp: _ } => {
+                    break;
+                }
+                sdl3::event::Event::MouseMotion { x, y, .. } => {
+                    mouse = Some((x, y));
+                    Self::present_sdl3(&mut canvas, &texture, clear_color, mouse)?;
+                }
+                sdl3::event::Event::Window {
+                    win_event: sdl3::event::WindowEvent::MouseLeave,
+                    ..
+                } => {
+                    mouse = None;
+                    Self::present_sdl3(&mut canvas, &texture, clear_color, mouse)?;
+                }
+                sdl3::event::Event::Window {
+                    win_event: sdl3::event::WindowEvent::Exposed,
+                    ..
+                } => {
+                    Self::present_sdl3(&mut canvas, &texture, clear_color, mouse)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok::<_, sdl3::Error>(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle(points: [(f64, f64); 3], color: Vec3) -> Tri2 {
+        Tri2 {
+            points: points.map(|(x, y)| Vec2 { x, y }),
+            colors: [color; 3],
+            alpha: 1.0,
+        }
+    }
+
+    #[test]
+    fn threaded_matches_serial() {
+        let mut serial = Scene::random_params(30, 600.0, 1);
+        serial.render();
+        for threads in [1, 3, 7] {
+            let mut threaded = Scene::random_params(30, 600.0, 1);
+            threaded.render_threaded(threads);
+            assert_eq!(serial.output, threaded.output, "{threads} threads");
+        }
+    }
+
+    #[test]
+    fn identical_points_are_degenerate() {
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        let degenerate = triangle([(3.0, 4.0); 3], white);
+        assert!(degenerate.is_degenerate());
+        assert!(!triangle([(0.0, 0.0), (4.0, 0.0), (0.0, 4.0)], white).is_degenerate());
+
+        let mut scene = Scene::<Vec3>::sized(10, 10);
+        assert!(scene.add_triangle(degenerate));
+        scene.reject_degenerate = true;
+        assert!(!scene.add_triangle(degenerate));
+        assert_eq!(scene.triangles().len(), 1);
+    }
+
+    #[test]
+    fn oit_is_order_independent() {
+        let red = Tri2 {
+            alpha: 0.5,
+            ..triangle(
+                [(0.0, 0.0), (20.0, 0.0), (0.0, 20.0)],
+                Vec3::new(1.0, 0.0, 0.0),
+            )
+        };
+        let blue = Tri2 {
+            alpha: 0.5,
+            ..triangle(
+                [(20.0, 0.0), (20.0, 20.0), (0.0, 10.0)],
+                Vec3::new(0.0, 0.0, 1.0),
+            )
+        };
+        let render = |oit: bool, triangles: [Tri2; 2]| {
+            let mut scene = Scene::<Vec3>::sized(20, 20);
+            scene.set_oit(oit);
+            scene.extend_triangles(triangles);
+            scene.render();
+            scene
+        };
+        let (forward, backward) = (render(true, [red, blue]), render(true, [blue, red]));
+        assert!(forward.output == backward.output);
+        let overlap = forward.pixel(8, 8);
+        assert!(overlap.r() > 0.0 && overlap.b() > 0.0, "{overlap:?}");
+        // without it, whichever is on top shows more
+        assert_ne!(
+            render(false, [red, blue]).pixel(8, 8),
+            render(false, [blue, red]).pixel(8, 8)
+        );
+    }
+
+    #[test]
+    fn barycentric_round_trips() {
+        let tri = [
+            Vec2 { x: 1.0, y: 2.0 },
+            Vec2 { x: 9.0, y: 3.0 },
+            Vec2 { x: 4.0, y: 8.0 },
+        ];
+        for p in [
+            Vec2 { x: 4.0, y: 4.0 },
+            Vec2 { x: 1.0, y: 2.0 },
+            Vec2 { x: 6.5, y: 5.5 },
+            Vec2 { x: -3.0, y: 10.0 },
+        ] {
+            let weights = point_to_bary(tri, p).unwrap();
+            assert!((weights.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+            let back = bary_to_point(tri, weights);
+            let offset = back - p;
+            assert!(offset.dot(offset) < 1e-20, "{p:?} came back as {back:?}");
+        }
+        let weights = [0.2, 0.3, 0.5];
+        let round_trip = point_to_bary(tri, bary_to_point(tri, weights)).unwrap();
+        assert!(
+            weights
+                .iter()
+                .zip(round_trip)
+                .all(|(a, b)| (a - b).abs() < 1e-12)
+        );
+        // a flat triangle has no barycentric coordinates
+        assert_eq!(point_to_bary([tri[0]; 3], tri[1]), None);
+    }
+
+    #[test]
+    fn pixel_formats_pack_bytes() {
+        let color = Vec3::new(1.0, 0.5, 0.0);
+        assert_eq!(color.to_rgb8(), [255, 128, 0]);
+        for (format, bytes) in [
+            (PixelFormat::Rgb24, vec![255, 128, 0]),
+            (PixelFormat::Bgr24, vec![0, 128, 255]),
+            (PixelFormat::Rgba32, vec![255, 128, 0, 255]),
+            (PixelFormat::Bgra32, vec![0, 128, 255, 255]),
+        ] {
+            assert_eq!(
+                format.pack(color)[..format.bytes_per_pixel()],
+                bytes,
+                "{format:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn bounds_are_the_union_of_triangles() {
+        let mut scene = Scene::<Vec3>::sized(10, 10);
+        assert_eq!(scene.bounds(), None);
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        scene.add_triangle(triangle([(1.0, 2.0), (4.0, 3.0), (2.0, 5.0)], white));
+        scene.add_triangle(triangle([(-3.0, 4.0), (0.0, 9.0), (3.0, 6.0)], white));
+        assert_eq!(
+            scene.bounds(),
+            Some((Vec2 { x: -3.0, y: 2.0 }, Vec2 { x: 4.0, y: 9.0 }))
+        );
+    }
+
+    #[test]
+    fn auto_exposure_brightens_dim_output() {
+        let mut scene = Scene::<Vec3>::sized(8, 8);
+        scene.clear_color = Vec3::new(0.1, 0.1, 0.1);
+        scene.clear_color_buffer();
+        scene.auto_exposure(99.0);
+        let max = scene
+            .output
+            .iter()
+            .map(|color| color.r())
+            .fold(0.0, f64::max);
+        assert!((max - 1.0).abs() < 1e-9, "{max}");
+
+        // nothing to expose
+        Scene::with_size(0, 0).auto_exposure(99.0);
+    }
+
+    #[test]
+    fn clearing_one_buffer_keeps_the_other() {
+        let mut scene = Scene::<Vec3>::sized(40, 30);
+        scene.clear_color = Vec3::new(0.0, 0.0, 1.0);
+        scene.add_mesh(Mesh::cube(1.0));
+        scene.render();
+        let (color, depth) = (scene.pixel(20, 15), scene.depth(20, 15));
+        assert!(depth.is_finite());
+        assert_ne!(color, scene.clear_color);
+
+        scene.clear_color_buffer();
+        assert_eq!(scene.pixel(20, 15), scene.clear_color);
+        assert_eq!(scene.depth(20, 15), depth);
+
+        // render doesn't clear by default, so the cube would fail the depth test against itself
+        scene.clear_on_render = true;
+        scene.render();
+        scene.clear_depth_buffer();
+        assert_eq!(scene.pixel(20, 15), color);
+        assert_eq!(scene.depth(20, 15), f64::INFINITY);
+    }
+
+    #[test]
+    fn swizzles_reorder_components() {
+        let v = Vec3::new(1.0, 2.0, 3.0);
+        assert_eq!(v.zyx(), Vec3::new(3.0, 2.0, 1.0));
+        assert_eq!(v.yzx(), Vec3::new(2.0, 3.0, 1.0));
+        assert_eq!(v.xz(), Vec2 { x: 1.0, y: 3.0 });
+        assert_eq!(v.zy(), Vec2 { x: 3.0, y: 2.0 });
+    }
+
+    #[test]
+    fn merge_appends_triangles() {
+        let color = Vec3::new(0.0, 1.0, 0.0);
+        let mut scene = Scene::<Vec3>::sized(50, 50);
+        scene.extend_triangles((0..20).map(|i| {
+            let x = i as f64 * 2.0;
+            triangle([(x, 0.0), (x + 2.0, 0.0), (x, 2.0)], color)
+        }));
+        let mut other = Scene::<Vec3>::sized(50, 50);
+        other.add_triangle(triangle([(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)], color));
+        scene.merge(&other);
+        assert_eq!(scene.triangles.len(), 21);
+
+        let smaller = Scene::<Vec3>::sized(10, 10);
+        assert!(scene.merge_with_output(&smaller).is_err());
+        assert_eq!(scene.triangles.len(), 21);
+    }
+
+    #[test]
+    fn depth_to_framebuffer_is_black_near_and_white_far() {
+        let mut scene = Scene::<Vec3>::sized(3, 1);
+        scene.depth.copy_from_slice(&[0.25, 0.75, 0.5]);
+        scene.depth_to_framebuffer(0.25, 0.75);
+        assert!(scene.pixel(0, 0).length() < 1e-9);
+        assert!((scene.pixel(1, 0) - Vec3::new(1.0, 1.0, 1.0)).length() < 1e-9);
+        assert!((scene.pixel(2, 0).r() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn reversed_z_keeps_nearer_geometry() {
+        let flat = |z, color| Tri3 {
+            points: [
+                Vec3::new(-3.0, -3.0, z),
+                Vec3::new(3.0, -3.0, z),
+                Vec3::new(0.0, 3.0, z),
+            ],
+            color,
+            material: 0,
+        };
+        let near = flat(1.0, Vec3::new(0.0, 1.0, 0.0));
+        let far = flat(-1.0, Vec3::new(1.0, 0.0, 0.0));
+        let mut only_far = Scene::<Vec3>::sized(20, 20);
+        only_far.set_reversed_z(true);
+        only_far.add_triangle_3d(far);
+        only_far.render();
+        for order in [[near, far], [far, near]] {
+            let mut scene = Scene::<Vec3>::sized(20, 20);
+            scene.set_reversed_z(true);
+            for triangle in order {
+                scene.add_triangle_3d(triangle);
+            }
+            scene.render();
+            assert_eq!(scene.pixel(10, 10), Vec3::new(0.0, 1.0, 0.0));
+            // the near plane is at 1.0, so nearer is higher
+            assert!(scene.depth(10, 10) > only_far.depth(10, 10));
+        }
+    }
+
+    #[test]
+    fn srgb_round_trips_and_is_piecewise() {
+        let mid = Vec3::new(0.5, 0.25, 0.75);
+        assert!((mid.srgb_to_linear().linear_to_srgb() - mid).length() < 1e-9);
+        assert!((mid.linear_to_srgb().srgb_to_linear() - mid).length() < 1e-9);
+
+        // the linear segment at the low end is far from a pure 2.2 gamma
+        let low = 0.02;
+        let srgb = Vec3::new(low, low, low).srgb_to_linear().r();
+        assert!((srgb - low / 12.92).abs() < 1e-12);
+        assert!((srgb - low.powf(2.2)).abs() > 1e-3);
+    }
+
+    #[test]
+    fn triangles_sort_back_to_front() {
+        let mut scene = Scene::<Vec3>::sized(20, 20);
+        for z in [0.0, -5.0, 2.0] {
+            scene.add_triangle_3d(Tri3 {
+                points: [
+                    Vec3::new(0.0, 0.0, z),
+                    Vec3::new(1.0, 0.0, z),
+                    Vec3::new(0.0, 1.0, z),
+                ],
+                color: Vec3::new(1.0, 1.0, 1.0),
+                material: 0,
+            });
+        }
+        let depths = scene
+            .triangles_sorted_by_depth()
+            .iter()
+            .map(|triangle| triangle.points[0].z())
+            .collect::<Vec<f64>>();
+        assert_eq!(depths, [-5.0, 0.0, 2.0]);
+        // it doesn't reorder the Scene's own triangles
+        assert_eq!(scene.triangles_3d()[0].points[0].z(), 0.0);
+    }
+
+    #[test]
+    fn points_on_an_edge_are_contained() {
+        let tri = triangle(
+            [(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)],
+            Vec3::new(1.0, 1.0, 1.0),
+        );
+        let on_edge = Vec2 { x: 5.0, y: 5.0 };
+        assert!(tri.contains(on_edge, 1e-9));
+        assert!(tri.contains(Vec2 { x: 5.0, y: 0.0 }, 1e-9));
+        assert!(!tri.contains(Vec2 { x: 5.1, y: 5.1 }, 1e-9));
+        assert!(tri.contains(Vec2 { x: 5.1, y: 5.1 }, 0.1));
+    }
+
+    #[test]
+    fn window_clear_color_bytes() {
+        let mut scene = Scene::<Vec3>::sized(4, 4);
+        assert_eq!(scene.window_clear_color.to_rgb8(), [0, 0, 0]);
+        scene.window_clear_color = Vec3::new(1.0, 0.5, -0.25);
+        assert_eq!(scene.window_clear_color.to_rgb8(), [255, 128, 0]);
+    }
+
+    #[test]
+    fn off_screen_triangles_are_culled() {
+        let color = Vec3::new(1.0, 1.0, 1.0);
+        let mut scene = Scene::<Vec3>::sized(20, 20);
+        scene.add_triangle(triangle(
+            [(100.0, 100.0), (110.0, 100.0), (100.0, 110.0)],
+            color,
+        ));
+        scene.add_triangle(triangle([(-30.0, 5.0), (-20.0, 5.0), (-30.0, 15.0)], color));
+        scene.add_triangle(triangle([(0.0, 0.0), (5.0, 0.0), (0.0, 5.0)], color));
+        scene.render();
+        let stats = scene.stats();
+        assert_eq!(stats.triangles_drawn, 1);
+        assert_eq!(stats.triangles_culled, 2);
+        let lit = scene.output.iter().filter(|&&pixel| pixel == color).count();
+        assert!(lit > 0 && lit < 25);
+    }
+
+    #[test]
+    fn hashable_vec3_deduplicates() {
+        let set = [
+            Vec3::new(1.0, 2.0, 3.0),
+            Vec3::new(1.0, 2.0, 3.0),
+            Vec3::new(0.0, -0.0, f64::NAN),
+            Vec3::new(-0.0, 0.0, -f64::NAN),
+        ]
+        .into_iter()
+        .map(HashableVec3)
+        .collect::<std::collections::HashSet<HashableVec3>>();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn motion_blur_leaves_a_dimmer_trail() {
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        let mut scene = Scene::<Vec3>::sized(20, 10);
+        scene.render_motion_blur(2, |scene, time| {
+            let x = time * 10.0;
+            scene.triangles.clear();
+            scene.add_triangle(triangle([(x, 0.0), (x + 8.0, 0.0), (x, 8.0)], white));
+        });
+        // each position is covered in one of the two sub-frames
+        for x in [1, 11] {
+            assert!((scene.pixel(x, 1) - white * 0.5).length() < 1e-9);
+        }
+        assert_eq!(scene.pixel(19, 9), Vec3::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn edge_antialiasing_ramps_across_the_edge() {
+        let mut scene = Scene::<Vec3>::sized(40, 30);
+        scene.edge_antialiasing = true;
+        // the top edge rises 1 pixel across the whole output
+        scene.add_triangle(triangle(
+            [(-10.0, 10.0), (50.0, 11.5), (-10.0, 100.0)],
+            Vec3::new(1.0, 1.0, 1.0),
+        ));
+        scene.render();
+        for x in [5, 20, 35] {
+            let column = (7..15).map(|y| scene.pixel(x, y).r()).collect::<Vec<f64>>();
+            assert_eq!(column[0], 0.0);
+            assert_eq!(column[7], 1.0);
+            assert!(column.windows(2).all(|pair| pair[0] <= pair[1]));
+            assert!(
+                column
+                    .iter()
+                    .any(|&coverage| coverage > 0.05 && coverage < 0.95)
+            );
+        }
+        // along the boundary row the coverage changes a little at a time
+        let row = (0..40)
+            .map(|x| scene.pixel(x, 11).r())
+            .collect::<Vec<f64>>();
+        assert!(row.windows(2).all(|pair| (pair[0] - pair[1]).abs() < 0.05));
+        assert!(row[0] > row[39] + 0.5);
+    }
+
+    #[test]
+    fn seeded_rng_gives_the_same_scene() {
+        let a = Scene::new_with_rng(&mut StdRng::seed_from_u64(7), 5);
+        let b = Scene::new_with_rng(&mut StdRng::seed_from_u64(7), 5);
+        let c = Scene::new_with_rng(&mut StdRng::seed_from_u64(8), 5);
+        assert_eq!(a.triangles.len(), 5);
+        assert_eq!(a.triangles, b.triangles);
+        assert_ne!(a.triangles, c.triangles);
+    }
+
+    #[test]
+    fn half_screen_triangle_covers_half() {
+        let tri = triangle(
+            [(0.0, 0.0), (40.0, 0.0), (0.0, 20.0)],
+            Vec3::new(1.0, 1.0, 1.0),
+        );
+        assert!((tri.screen_coverage(40, 20) - 0.5).abs() < 1e-9);
+        // the winding doesn't matter, and it never covers more than the whole screen
+        let [a, b, c] = tri.points;
+        let reversed = Tri2 {
+            points: [a, c, b],
+            ..tri
+        };
+        assert!((reversed.screen_coverage(40, 20) - 0.5).abs() < 1e-9);
+        assert_eq!(tri.screen_coverage(4, 2), 1.0);
+    }
+
+    #[test]
+    fn flat_triangles_fill_uniformly_and_colors_interpolate() {
+        let points = [
+            Vec2 { x: 0.0, y: 0.0 },
+            Vec2 { x: 30.0, y: 0.0 },
+            Vec2 { x: 0.0, y: 30.0 },
+        ];
+        let background = Vec3::new(0.0, 0.0, 0.0);
+        let color = Vec3::new(0.2, 0.4, 0.6);
+        let mut flat = Scene::<Vec3>::sized(30, 30);
+        flat.add_triangle(Tri2::flat(points, color));
+        flat.render();
+        let covered = flat
+            .output
+            .iter()
+            .filter(|&&pixel| pixel != background)
+            .collect::<Vec<&Vec3>>();
+        assert!(covered.len() > 300);
+        assert!(covered.iter().all(|&&pixel| pixel == color));
+
+        let mut shaded = Scene::<Vec3>::sized(30, 30);
+        shaded.add_triangle(Tri2 {
+            points,
+            colors: [
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0),
+            ],
+            alpha: 1.0,
+        });
+        shaded.render();
+        let third = Vec3::new(1.0, 1.0, 1.0) * (1.0 / 3.0);
+        assert!((shaded.pixel(10, 10) - third).length() < 0.1);
+        assert!(shaded.pixel(25, 1).g() > 0.7);
+        assert!(shaded.pixel(1, 25).b() > 0.7);
+    }
+
+    #[test]
+    fn prepared_triangles_match_inside() {
+        let color = Vec3::new(1.0, 1.0, 1.0);
+        for tri in [
+            triangle([(1.0, 2.0), (17.0, 5.0), (6.0, 15.0)], color),
+            triangle([(1.0, 2.0), (6.0, 15.0), (17.0, 5.0)], color),
+        ] {
+            let prepared = tri.prepared();
+            for y in 0..40 {
+                for x in 0..40 {
+                    let point = Vec2 {
+                        x: x as f64 * 0.5,
+                        y: y as f64 * 0.5,
+                    };
+                    assert_eq!(prepared.inside(point), tri.inside(point), "{point:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blit_copies_overlapping_regions() {
+        let mut scene = Scene::<Vec3>::sized(12, 12);
+        let color = |x: usize, y: usize| Vec3::new(x as f64 / 10.0, y as f64 / 10.0, 1.0);
+        for y in 2..6 {
+            for x in 2..6 {
+                scene.output[y * 12 + x] = color(x, y);
+            }
+        }
+        scene.blit(
+            (Vec2 { x: 2.0, y: 2.0 }, Vec2 { x: 6.0, y: 6.0 }),
+            Vec2 { x: 4.0, y: 3.0 },
+        );
+        for y in 0..4 {
+            for x in 0..4 {
+                assert_eq!(scene.pixel(x + 4, y + 3), color(x + 2, y + 2));
+            }
+        }
+        // the part of the source that wasn't overwritten is still there
+        assert_eq!(scene.pixel(2, 2), color(2, 2));
+        assert_eq!(scene.pixel(3, 5), color(3, 5));
+
+        // parts that land outside the output are clipped
+        scene.blit(
+            (Vec2 { x: 4.0, y: 3.0 }, Vec2 { x: 8.0, y: 7.0 }),
+            Vec2 { x: 10.0, y: -2.0 },
+        );
+        assert_eq!(scene.pixel(11, 0), color(3, 4));
+    }
+
+    #[test]
+    fn non_finite_triangles_are_skipped() {
+        assert!(Vec3::new(1.0, 2.0, 3.0).is_finite());
+        assert!(!Vec3::new(1.0, f64::INFINITY, 3.0).is_finite());
+        assert!(
+            !Vec2 {
+                x: f64::NAN,
+                y: 0.0
+            }
+            .is_finite()
+        );
+
+        let mut scene = Scene::<Vec3>::sized(20, 20);
+        scene.add_triangle(triangle(
+            [(0.0, 0.0), (f64::NAN, 0.0), (0.0, 10.0)],
+            Vec3::new(1.0, 1.0, 1.0),
+        ));
+        scene.add_triangle(triangle(
+            [(0.0, 0.0), (f64::INFINITY, 0.0), (0.0, f64::NEG_INFINITY)],
+            Vec3::new(1.0, 1.0, 1.0),
+        ));
+        scene.render();
+        assert_eq!(scene.stats().triangles_drawn, 0);
+        assert!(
+            scene
+                .output
+                .iter()
+                .all(|&pixel| pixel == Vec3::new(0.0, 0.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn dithered_gradient_uses_the_palette_gradually() {
+        let (black, white) = (Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0));
+        let gradient = |scene: &mut Scene<Vec3>| {
+            for y in 0..8 {
+                for x in 0..32 {
+                    scene.output[y * 32 + x] = white * (x as f64 / 31.0);
+                }
+            }
+        };
+        let mut scene = Scene::<Vec3>::sized(32, 8);
+        gradient(&mut scene);
+        scene.quantize(&[black, white], true);
+        assert!(
+            scene
+                .output
+                .iter()
+                .all(|&pixel| pixel == black || pixel == white)
+        );
+        let white_in = |scene: &Scene<Vec3>, xs: std::ops::Range<usize>| {
+            xs.flat_map(|x| (0..8).map(move |y| (x, y)))
+                .filter(|&(x, y)| scene.pixel(x, y) == white)
+                .count()
+        };
+        let quarters = [0..8, 8..16, 16..24, 24..32].map(|xs| white_in(&scene, xs));
+        assert!(
+            quarters.windows(2).all(|pair| pair[0] < pair[1]),
+            "{quarters:?}"
+        );
+        // the middle quarters are a mix, not a solid band
+        assert!(quarters[1] > 0 && quarters[2] < 64);
+
+        // without dithering, each column is a single color
+        gradient(&mut scene);
+        scene.quantize(&[black, white], false);
+        assert_eq!(white_in(&scene, 0..16), 0);
+        assert_eq!(white_in(&scene, 16..32), 128);
+    }
+
+    #[test]
+    fn far_meshes_use_the_lowest_detail() {
+        let scene = Scene::<Vec3>::sized(200, 200);
+        let mesh = Mesh::uv_sphere(16, 32, 1.5).with_lods(vec![
+            Mesh::uv_sphere(8, 16, 1.5),
+            Mesh::uv_sphere(4, 8, 1.5),
+        ]);
+        assert_eq!(scene.lod_index(&mesh), 0);
+
+        let mut far = mesh.clone();
+        for vertex in &mut far.vertices {
+            (*vertex) += Vec3::new(0.0, 0.0, -90.0);
+        }
+        assert_eq!(scene.lod_index(&far), far.lods.len());
+        assert_eq!(scene.select_lod(&far).triangle_count(), 8 * (2 * 4 - 2));
+    }
+
+    #[test]
+    fn front_face_decides_which_triangle_is_culled() {
+        let red = Vec3::new(1.0, 0.0, 0.0);
+        let blue = Vec3::new(0.0, 0.0, 1.0);
+        // counter-clockwise seen from the camera on the left, clockwise on the right
+        let ccw = Tri3 {
+            points: [
+                Vec3::new(-2.0, -1.0, 0.0),
+                Vec3::new(-0.5, -1.0, 0.0),
+                Vec3::new(-2.0, 1.0, 0.0),
+            ],
+            color: red,
+            material: 0,
+        };
+        let cw = Tri3 {
+            points: [
+                Vec3::new(0.5, -1.0, 0.0),
+                Vec3::new(0.5, 1.0, 0.0),
+                Vec3::new(2.0, -1.0, 0.0),
+            ],
+            color: blue,
+            material: 0,
+        };
+        for (front_face, drawn) in [(FrontFace::Ccw, red), (FrontFace::Cw, blue)] {
+            let mut scene = Scene::<Vec3>::sized(40, 40);
+            scene.backface_culling = true;
+            scene.front_face = front_face;
+            scene.add_triangle_3d(ccw);
+            scene.add_triangle_3d(cw);
+            scene.render();
+            assert_eq!(scene.stats().triangles_drawn, 1);
+            let colors = scene
+                .output
+                .iter()
+                .filter(|&&pixel| pixel != Vec3::new(0.0, 0.0, 0.0))
+                .collect::<Vec<&Vec3>>();
+            assert!(!colors.is_empty());
+            assert!(colors.iter().all(|&&pixel| pixel == drawn));
+        }
+    }
+
+    #[test]
+    fn flooring_rounds_negative_coordinates_down() {
+        let floor = |x, y| Vec2 { x, y }.floor_to_ivec2();
+        assert_eq!(floor(1.7, 2.0), IVec2 { x: 1, y: 2 });
+        assert_eq!(floor(-0.5, -1.0), IVec2 { x: -1, y: -1 });
+        assert_eq!(floor(-1.0001, -0.0), IVec2 { x: -2, y: 0 });
+        assert_eq!(floor(f64::NAN, -1e20), IVec2 { x: 0, y: i32::MIN });
+        assert_eq!(
+            Vec2 { x: -0.5, y: 1.2 }.ceil_to_ivec2(),
+            IVec2 { x: 0, y: 2 }
+        );
+    }
+
+    #[test]
+    fn phong_highlight_is_brightest_at_the_mirror_angle() {
+        let mut scene = Scene::<Vec3>::sized(60, 60);
+        scene.lights.push(Light {
+            direction: Vec3::new(0.0, 0.0, 1.0),
+            color: Vec3::new(1.0, 1.0, 1.0),
+        });
+        let plane = Mesh {
+            vertices: vec![
+                Vec3::new(-3.0, -3.0, 0.0),
+                Vec3::new(3.0, -3.0, 0.0),
+                Vec3::new(3.0, 3.0, 0.0),
+                Vec3::new(-3.0, 3.0, 0.0),
+            ],
+            normals: vec![Vec3::new(0.0, 0.0, 1.0); 4],
+            indices: vec![[0, 1, 2], [0, 2, 3]],
+            color: Vec3::new(0.2, 0.2, 0.2),
+            specular: Specular {
+                color: Vec3::new(1.0, 1.0, 1.0),
+                shininess: 32.0,
+            },
+            lods: Vec::new(),
+        };
+        scene.add_mesh(plane);
+        scene.render();
+        // the light and the camera are both straight in front of the middle
+        let middle = scene.pixel(30, 30).r();
+        let off_angle = scene.pixel(52, 30).r();
+        assert!(middle > 1.0, "{middle}");
+        assert!(off_angle < 0.5, "{off_angle}");
+        // ambient and diffuse light are still there
+        assert!(off_angle > 0.2 * 1.1 - 1e-9);
+    }
+
+    #[test]
+    fn lighting_uses_the_front_face_normal() {
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        // clockwise seen from the camera
+        let points = [
+            Vec3::new(-1.0, -1.0, 0.0),
+            Vec3::new(-1.0, 1.0, 0.0),
+            Vec3::new(1.0, -1.0, 0.0),
+        ];
+        // diffuse light from straight in front, plus the default ambient light
+        let expected = white * 1.1;
+        let lit = |add: fn(&mut Scene<Vec3>, [Vec3; 3])| {
+            let mut scene = Scene::<Vec3>::sized(40, 40);
+            scene.front_face = FrontFace::Cw;
+            scene.lights.push(Light {
+                direction: Vec3::new(0.0, 0.0, 1.0),
+                color: white,
+            });
+            add(&mut scene, points);
+            scene.render();
+            scene.pixel(17, 22)
+        };
+        let triangle = lit(|scene, points| {
+            scene.add_triangle_3d(Tri3 {
+                points,
+                color: Vec3::new(1.0, 1.0, 1.0),
+                material: 0,
+            })
+        });
+        assert!((triangle - expected).length() < 1e-9, "{triangle:?}");
+        // a mesh without vertex normals falls back to the front face normal too
+        let mesh = lit(|scene, points| {
+            scene.add_mesh(Mesh {
+                vertices: points.to_vec(),
+                normals: Vec::new(),
+                indices: vec![[0, 1, 2]],
+                color: Vec3::new(1.0, 1.0, 1.0),
+                specular: Specular::default(),
+                lods: Vec::new(),
+            })
+        });
+        assert!((mesh - expected).length() < 1e-9, "{mesh:?}");
+    }
+
+    #[test]
+    fn coverage_mask_matches_the_rendered_pixels() {
+        let mut scene = Scene::<Vec3>::sized(50, 50);
+        scene.extend_triangles(Scene::random_params(6, 50.0, 3).triangles);
+        let mask = scene.coverage_mask();
+        assert_eq!(mask.len(), 50 * 50);
+        assert!(
+            mask.iter()
+                .all(|&coverage| coverage == 0.0 || coverage == 1.0)
+        );
+
+        scene.clear_color = Vec3::new(-1.0, -1.0, -1.0);
+        scene.clear_color_buffer();
+        scene.render();
+        let colored = scene
+            .output
+            .iter()
+            .filter(|&&pixel| pixel != Vec3::new(-1.0, -1.0, -1.0))
+            .count();
+        assert!(colored > 0);
+        assert_eq!(mask.iter().sum::<f64>(), colored as f64);
+    }
+
+    #[test]
+    fn drawing_triangles_one_by_one_matches_a_render() {
+        let triangles = [
+            triangle(
+                [(2.0, 2.0), (30.0, 4.0), (8.0, 25.0)],
+                Vec3::new(1.0, 0.0, 0.0),
+            ),
+            triangle(
+                [(10.0, 0.0), (38.0, 20.0), (5.0, 35.0)],
+                Vec3::new(0.0, 1.0, 0.0),
+            ),
+            Tri2 {
+                alpha: 0.5,
+                ..triangle(
+                    [(0.0, 39.0), (39.0, 39.0), (20.0, 10.0)],
+                    Vec3::new(0.0, 0.0, 1.0),
+                )
+            },
+        ];
+        let mut batched = Scene::<Vec3>::sized(40, 40);
+        batched.edge_antialiasing = true;
+        batched.extend_triangles(triangles);
+        batched.render();
+
+        let mut incremental = Scene::<Vec3>::sized(40, 40);
+        incremental.edge_antialiasing = true;
+        for triangle in triangles {
+            incremental.draw_triangle(triangle);
+        }
+        assert!(incremental.output == batched.output);
+    }
+
+    #[test]
+    fn always_lets_farther_triangles_overwrite() {
+        let near = Tri3 {
+            points: [
+                Vec3::new(-2.0, -2.0, 1.0),
+                Vec3::new(2.0, -2.0, 1.0),
+                Vec3::new(0.0, 2.0, 1.0),
+            ],
+            color: Vec3::new(0.0, 1.0, 0.0),
+            material: 0,
+        };
+        let far = Tri3 {
+            points: near.points.map(|point| point - Vec3::new(0.0, 0.0, 3.0)),
+            color: Vec3::new(1.0, 0.0, 0.0),
+            material: 0,
+        };
+        for (depth_func, expected) in [
+            (DepthFunc::Less, near.color),
+            (DepthFunc::Always, far.color),
+        ] {
+            let mut scene = Scene::<Vec3>::sized(20, 20);
+            scene.set_depth_func(depth_func);
+            scene.add_triangle_3d(near);
+            scene.add_triangle_3d(far);
+            scene.render();
+            assert_eq!(scene.pixel(10, 10), expected);
+        }
+    }
+
+    #[test]
+    fn per_channel_functions() {
+        let v = Vec3::new(1.0, 4.0, 9.0);
+        assert_eq!(v.map(|value| value * 2.0), Vec3::new(2.0, 8.0, 18.0));
+        assert_eq!(v.sqrt(), Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(v.powf(0.5), Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(v.powf(2.0), Vec3::new(1.0, 16.0, 81.0));
+    }
+
+    #[test]
+    fn pick_finds_the_nearest_triangle() {
+        let at = |z| Tri3 {
+            points: [
+                Vec3::new(-2.0, -2.0, z),
+                Vec3::new(2.0, -2.0, z),
+                Vec3::new(0.0, 2.0, z),
+            ],
+            color: Vec3::new(1.0, 1.0, 1.0),
+            material: 0,
+        };
+        let mut scene = Scene::<Vec3>::sized(40, 40);
+        scene.add_triangle_3d(at(-1.0));
+        scene.add_triangle_3d(at(1.0));
+        scene.add_triangle_3d(at(0.0));
+        assert_eq!(scene.pick(Vec2 { x: 20.0, y: 20.0 }), Some(1));
+        assert_eq!(scene.pick(Vec2 { x: 1.0, y: 1.0 }), None);
+    }
+
+    #[test]
+    fn unchanged_scenes_skip_rendering() {
+        let mut scene = Scene::<Vec3>::sized(20, 20);
+        scene.add_triangle(triangle(
+            [(0.0, 0.0), (15.0, 0.0), (0.0, 15.0)],
+            Vec3::new(1.0, 1.0, 1.0),
+        ));
+        scene.render();
+        let first = scene.stats();
+        assert!(!first.skipped);
+        let output = scene.output.clone();
+
+        scene.render();
+        let second = scene.stats();
+        assert!(second.skipped);
+        assert_eq!(
+            RenderStats {
+                skipped: false,
+                render_time: first.render_time,
+                ..second
+            },
+            first
+        );
+        assert!(scene.output == output);
+
+        // changing anything renders again
+        scene.add_triangle(triangle(
+            [(19.0, 19.0), (10.0, 19.0), (19.0, 10.0)],
+            Vec3::new(1.0, 0.0, 0.0),
+        ));
+        scene.render();
+        assert!(!scene.stats().skipped);
+        assert_eq!(scene.pixel(18, 18), Vec3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn ssao_darkens_pits_but_not_flat_areas() {
+        let mut scene = Scene::<Vec3>::sized(21, 21);
+        scene.output.fill(Vec3::new(1.0, 1.0, 1.0));
+        // a slope, with a pit in the middle that everything around it is nearer than
+        for y in 0..21 {
+            for x in 0..21 {
+                scene.depth[y * 21 + x] = 0.3 + 0.01 * x as f64;
+            }
+        }
+        scene.depth[10 * 21 + 10] = 0.9;
+        scene.apply_ssao(3.0, 0.8);
+        assert!(scene.pixel(10, 10).r() < 0.5);
+        assert_eq!(scene.pixel(3, 16), Vec3::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn projection_and_rejection_sum_back() {
+        let v = Vec2 { x: 3.0, y: 4.0 };
+        let x_axis = Vec2 { x: 2.0, y: 0.0 };
+        assert_eq!(v.project_onto(x_axis), Vec2 { x: 3.0, y: 0.0 });
+        assert_eq!(v.reject_from(x_axis), Vec2 { x: 0.0, y: 4.0 });
+        let diagonal = Vec2 { x: 1.0, y: 1.0 };
+        let (projected, rejected) = (v.project_onto(diagonal), v.reject_from(diagonal));
+        assert!((projected + rejected - v).dot(projected + rejected - v) < 1e-20);
+        assert!(rejected.dot(diagonal).abs() < 1e-12);
+    }
+
+    #[test]
+    fn render_only_draws_just_one_triangle() {
+        let mut scene = Scene::<Vec3>::sized(30, 30);
+        scene.clear_color = Vec3::new(0.0, 0.0, 0.5);
+        scene.add_triangle(triangle(
+            [(0.0, 0.0), (30.0, 0.0), (0.0, 30.0)],
+            Vec3::new(1.0, 0.0, 0.0),
+        ));
+        scene.add_triangle(triangle(
+            [(20.0, 20.0), (29.0, 20.0), (20.0, 29.0)],
+            Vec3::new(0.0, 1.0, 0.0),
+        ));
+        scene.output.fill(Vec3::new(1.0, 1.0, 1.0));
+        scene.render_only(1).unwrap();
+        assert_eq!(scene.pixel(22, 22), Vec3::new(0.0, 1.0, 0.0));
+        let colored = scene
+            .output
+            .iter()
+            .filter(|&&pixel| pixel != scene.clear_color)
+            .count();
+        assert!(colored > 0 && colored < 50);
+        assert_eq!(scene.pixel(2, 2), scene.clear_color);
+        assert!(scene.render_only(2).is_err());
+    }
+
+    #[test]
+    fn gray_lands_in_the_middle_bin() {
+        let mut scene = Scene::<Vec3>::sized(8, 8);
+        scene.output.fill(Vec3::new(0.5, 0.5, 0.5));
+        assert_eq!(scene.luminance_histogram(3), [0, 64, 0]);
+        let histogram = scene.luminance_histogram(4);
+        assert_eq!(histogram.iter().sum::<usize>(), 64);
+        assert!(scene.luminance_histogram(0).is_empty());
+    }
+
+    #[test]
+    fn depth_bias_lets_coplanar_decals_win() {
+        // a slanted surface, with a decal on the same plane
+        let slanted = |points: [(f64, f64); 3], color| Tri3 {
+            points: points.map(|(x, y)| Vec3::new(x, y, -0.7 * x - 0.3 * y)),
+            color,
+            material: 0,
+        };
+        let base = slanted(
+            [(-3.0, -3.0), (3.0, -3.0), (0.0, 3.0)],
+            Vec3::new(0.0, 0.0, 1.0),
+        );
+        let decal_color = Vec3::new(1.0, 1.0, 0.0);
+        let decal = slanted([(-1.0, -1.0), (1.0, -1.0), (0.0, 1.0)], decal_color);
+
+        let decal_pixels = |bias: (f64, f64)| {
+            let mut scene = Scene::<Vec3>::sized(40, 40);
+            scene.add_triangle_3d(base);
+            scene.render();
+            scene.triangles_3d.clear();
+            scene.add_triangle_3d(decal);
+            scene.set_depth_bias(bias.0, bias.1);
+            scene.render();
+            let mut decal_only = Scene::<Vec3>::sized(40, 40);
+            decal_only.add_triangle_3d(decal);
+            decal_only.render();
+            let covered = (0..40 * 40)
+                .filter(|&i| decal_only.output[i] == decal_color)
+                .collect::<Vec<usize>>();
+            let won = covered
+                .iter()
+                .filter(|&&i| scene.output[i] == decal_color)
+                .count();
+            (won, covered.len())
+        };
+        let (won, covered) = decal_pixels((-1.0, -1.0));
+        assert!(covered > 20);
+        assert_eq!(won, covered);
+        // without the bias the decal loses at least some of the depth tests
+        let (won, covered) = decal_pixels((0.0, 0.0));
+        assert!(won < covered);
+    }
+
+    #[test]
+    fn orthonormal_basis_is_perpendicular() {
+        for normal in [
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(1.0, 2.0, 3.0).normalize(),
+            Vec3::new(-0.3, 0.1, -0.9).normalize(),
+        ] {
+            let (a, b) = normal.orthonormal_basis();
+            assert!(a.dot(normal).abs() < 1e-12);
+            assert!(b.dot(normal).abs() < 1e-12);
+            assert!(a.dot(b).abs() < 1e-12);
+            assert!((a.length() - 1.0).abs() < 1e-12);
+            assert!((b.length() - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn polyline_covers_both_segments_and_the_join() {
+        let mut scene = Scene::<Vec3>::sized(30, 30);
+        let red = Vec3::new(1.0, 0.0, 0.0);
+        scene.draw_polyline(
+            &[
+                Vec2 { x: 5.0, y: 5.0 },
+                Vec2 { x: 25.0, y: 5.0 },
+                Vec2 { x: 25.0, y: 25.0 },
+            ],
+            3.0,
+            red,
+        );
+        for (x, y) in [(10, 5), (20, 5), (25, 5), (25, 15), (25, 22)] {
+            assert_eq!(scene.pixel(x, y), red, "({x}, {y})");
+        }
+        // the corner of the join is filled in
+        assert!(scene.pixel(26, 4).r() > 0.5);
+        assert_eq!(scene.pixel(15, 15), Vec3::new(0.0, 0.0, 0.0));
+        // points outside the output are clipped
+        scene.draw_polyline(
+            &[Vec2 { x: -10.0, y: 28.0 }, Vec2 { x: 40.0, y: 28.0 }],
+            2.0,
+            red,
+        );
+        assert_eq!(scene.pixel(0, 28), red);
+    }
+
+    #[test]
+    fn random_triangles_stay_on_screen() {
+        let in_bounds = |scene: &Scene<Vec3>| {
+            let points = scene
+                .triangles
+                .iter()
+                .flat_map(|triangle| triangle.points)
+                .collect::<Vec<Vec2>>();
+            let inside = points
+                .iter()
+                .filter(|point| {
+                    (0.0..=scene.width as f64).contains(&point.x)
+                        && (0.0..=scene.height as f64).contains(&point.y)
+                })
+                .count();
+            inside as f64 / points.len() as f64
+        };
+        assert!(in_bounds(&Scene::random_params(100, 600.0, 5)) > 0.95);
+        assert!(in_bounds(&Scene::new_with_rng(&mut StdRng::seed_from_u64(5), 100)) > 0.95);
+        // a bigger scale spreads them further
+        let spread = Scene::random_params(100, 1200.0, 5);
+        assert!(in_bounds(&spread) < 0.75);
+    }
+
+    #[test]
+    fn triangles_sharing_a_material_match() {
+        let mut scene = Scene::<Vec3>::sized(40, 20);
+        scene.materials.push(Material {
+            base_color: Vec3::new(0.2, 0.6, 0.4),
+            ..Material::default()
+        });
+        let at = |x: f64, material| Tri3 {
+            points: [
+                Vec3::new(x - 1.0, -1.0, 0.0),
+                Vec3::new(x + 1.0, -1.0, 0.0),
+                Vec3::new(x, 1.0, 0.0),
+            ],
+            color: Vec3::new(1.0, 1.0, 1.0),
+            material,
+        };
+        scene.add_triangle_3d(at(-2.0, 1));
+        scene.add_triangle_3d(at(2.0, 1));
+        scene.add_triangle_3d(at(0.0, 0));
+        scene.render();
+        let (left, right) = (scene.pixel(13, 11), scene.pixel(27, 11));
+        assert_eq!(left, Vec3::new(0.2, 0.6, 0.4));
+        assert_eq!(left, right);
+        assert_eq!(scene.pixel(20, 11), Vec3::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn fragment_estimate_is_an_upper_bound() {
+        let mut scene = Scene::<Vec3>::sized(50, 50);
+        scene.extend_triangles(Scene::random_params(8, 60.0, 11).triangles);
+        scene.add_mesh(Mesh::cube(1.5));
+        let estimate = scene.estimated_fragment_count();
+        scene.render();
+        let shaded = scene.stats().pixels_shaded;
+        assert!(shaded > 0);
+        assert!(estimate >= shaded, "{estimate} < {shaded}");
+    }
+
+    #[test]
+    fn input_colors_are_clamped_when_asked() {
+        let bright = triangle(
+            [(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)],
+            Vec3::new(2.0, -0.5, 0.5),
+        );
+        for (clamp, expected) in [
+            (true, Vec3::new(1.0, 0.0, 0.5)),
+            (false, Vec3::new(2.0, -0.5, 0.5)),
+        ] {
+            let mut scene = Scene::<Vec3>::sized(10, 10);
+            scene.clamp_input_colors = clamp;
+            scene.add_triangle(bright);
+            scene.render();
+            assert_eq!(scene.pixel(2, 2), expected);
+        }
+    }
+
+    #[test]
+    fn bottom_left_origin_mirrors_vertically() {
+        let tri = triangle(
+            [(1.0, 1.0), (12.0, 2.0), (3.0, 9.0)],
+            Vec3::new(1.0, 1.0, 1.0),
+        );
+        let render = |origin| {
+            let mut scene = Scene::<Vec3>::sized(16, 12);
+            scene.origin = origin;
+            scene.add_triangle(tri);
+            scene.render();
+            scene
+        };
+        let (top, bottom) = (render(Origin::TopLeft), render(Origin::BottomLeft));
+        assert!(
+            top.output
+                .iter()
+                .any(|&pixel| pixel != Vec3::new(0.0, 0.0, 0.0))
+        );
+        for y in 0..12 {
+            for x in 0..16 {
+                assert_eq!(top.pixel(x, y), bottom.pixel(x, 11 - y), "({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn msaa_blends_crossing_triangles_in_every_render_path() {
+        // the green triangle cuts through the red one a little left of the middle, where some of
+        // the samples see each
+        let red = Tri3 {
+            points: [
+                Vec3::new(-2.0, -2.0, 0.0),
+                Vec3::new(2.0, -2.0, 0.0),
+                Vec3::new(0.0, 2.0, 0.0),
+            ],
+            color: Vec3::new(1.0, 0.0, 0.0),
+            material: 0,
+        };
+        let green = Tri3 {
+            points: [
+                Vec3::new(-2.0, -2.0, -1.7),
+                Vec3::new(2.0, -2.0, 2.3),
+                Vec3::new(0.0, 2.0, 0.3),
+            ],
+            color: Vec3::new(0.0, 1.0, 0.0),
+            material: 0,
+        };
+        let scene = |samples| {
+            let mut scene = Scene::<Vec3>::sized(20, 20);
+            scene.clear_on_render = true;
+            scene.set_msaa(samples);
+            scene.add_triangle_3d(red);
+            scene.add_triangle_3d(green);
+            scene
+        };
+        let blended = |scene: &Scene<Vec3>| {
+            scene
+                .output
+                .iter()
+                .any(|pixel| pixel.r() > 0.2 && pixel.g() > 0.2)
+        };
+
+        let mut aliased = scene(1);
+        aliased.render();
+        assert!(!blended(&aliased));
+        let mut rendered = scene(4);
+        rendered.render();
+        assert!(blended(&rendered));
+
+        for threads in [1, 3, 7] {
+            let mut threaded = scene(4);
+            threaded.render_threaded(threads);
+            assert!(threaded.output == rendered.output);
+            assert!(threaded.depth == rendered.depth);
+        }
+
+        // a 2D triangle drawn by itself gets smooth edges too
+        let mut drawn = Scene::<Vec3>::sized(20, 20);
+        drawn.set_msaa(4);
+        drawn.draw_triangle(Tri2::flat(
+            [
+                Vec2 { x: 0.0, y: 0.0 },
+                Vec2 { x: 20.0, y: 0.0 },
+                Vec2 { x: 0.0, y: 20.0 },
+            ],
+            Vec3::new(1.0, 1.0, 1.0),
+        ));
+        let edge = drawn.pixel(10, 10).r();
+        assert!(edge > 0.1 && edge < 0.9);
+    }
+
+    #[test]
+    fn hit_test_returns_the_last_drawn_triangle() {
+        let red = Vec3::new(1.0, 0.0, 0.0);
+        let blue = Vec3::new(0.0, 0.0, 1.0);
+        let mut scene = Scene::<Vec3>::sized(20, 20);
+        scene.add_triangle(triangle([(0.0, 0.0), (20.0, 0.0), (0.0, 20.0)], red));
+        scene.add_triangle(triangle([(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)], blue));
+
+        assert_eq!(scene.hit_test(Vec2 { x: 2.0, y: 2.0 }), Some((1, blue)));
+        // only the first one covers this point
+        assert_eq!(scene.hit_test(Vec2 { x: 8.0, y: 8.0 }), Some((0, red)));
+        assert_eq!(scene.hit_test(Vec2 { x: 15.0, y: 15.0 }), None);
+    }
+
+    #[test]
+    fn outline_surrounds_a_filled_triangle() {
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        let green = Vec3::new(0.0, 1.0, 0.0);
+        let mut scene = Scene::<Vec3>::sized(40, 40);
+        scene.add_triangle(triangle([(10.0, 10.0), (30.0, 10.0), (10.0, 30.0)], white));
+        scene.render();
+        scene.outline(2, green);
+
+        // just outside each edge
+        for (x, y) in [(9, 15), (15, 9), (21, 20)] {
+            assert_eq!(scene.pixel(x, y), green);
+        }
+        assert_eq!(scene.pixel(15, 15), white);
+        // farther away than the thickness
+        for (x, y) in [(5, 15), (15, 5), (25, 25)] {
+            assert_eq!(scene.pixel(x, y), Vec3::new(0.0, 0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn vectors_sum_and_multiply_over_iterators() {
+        let points = [
+            Vec3::new(1.0, 2.0, 3.0),
+            Vec3::new(4.0, 5.0, 6.0),
+            Vec3::new(7.0, 8.0, 9.0),
+        ];
+        let average = points.iter().sum::<Vec3>() * (1.0 / 3.0);
+        assert!((average - Vec3::new(4.0, 5.0, 6.0)).length() < 1e-12);
+        assert_eq!(
+            points.into_iter().product::<Vec3>(),
+            Vec3::new(28.0, 80.0, 162.0)
+        );
+        assert_eq!(
+            std::iter::empty::<Vec3>().sum::<Vec3>(),
+            Vec3::new(0.0, 0.0, 0.0)
+        );
+
+        let corners = [Vec2 { x: 1.0, y: 2.0 }, Vec2 { x: 3.0, y: 4.0 }];
+        assert_eq!(corners.iter().sum::<Vec2>(), Vec2 { x: 4.0, y: 6.0 });
+        assert_eq!(corners.iter().product::<Vec2>(), Vec2 { x: 3.0, y: 8.0 });
+    }
+
+    #[test]
+    fn barycentric_debug_mode_colors_vertices_primary() {
+        let mut scene = Scene::<Vec3>::sized(20, 20);
+        scene.debug_mode = DebugMode::Barycentric;
+        scene.add_triangle(triangle(
+            [(0.0, 0.0), (19.0, 0.0), (0.0, 19.0)],
+            Vec3::new(1.0, 1.0, 1.0),
+        ));
+        scene.render();
+        assert_eq!(scene.pixel(0, 0), Vec3::new(1.0, 0.0, 0.0));
+        let inside = scene.pixel(5, 5);
+        assert!((inside.r() + inside.g() + inside.b() - 1.0).abs() < 1e-9);
+
+        // a 3D triangle facing the camera shows its normal, (0, 0, 1)
+        let mut scene = Scene::<Vec3>::sized(20, 20);
+        scene.debug_mode = DebugMode::Normals;
+        scene.add_triangle_3d(Tri3 {
+            points: [
+                Vec3::new(-2.0, -2.0, 0.0),
+                Vec3::new(2.0, -2.0, 0.0),
+                Vec3::new(0.0, 2.0, 0.0),
+            ],
+            color: Vec3::new(1.0, 0.0, 0.0),
+            material: 0,
+        });
+        scene.render();
+        assert!((scene.pixel(10, 10) - Vec3::new(0.5, 0.5, 1.0)).length() < 1e-9);
+    }
+
+    #[test]
+    fn filled_circles_and_rectangles_stop_at_their_edges() {
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        let black = Vec3::new(0.0, 0.0, 0.0);
+        let mut scene = Scene::<Vec3>::sized(20, 20);
+        scene.fill_circle(Vec2 { x: 10.0, y: 10.0 }, 3.0, white);
+        assert_eq!(scene.pixel(10, 10), white);
+        assert_eq!(scene.pixel(13, 10), white);
+        assert_eq!(scene.pixel(14, 10), black);
+        assert_eq!(scene.pixel(13, 13), black);
+        // clipped to the output
+        scene.fill_circle(Vec2 { x: -5.0, y: 0.0 }, 6.0, white);
+        assert_eq!(scene.pixel(0, 0), white);
+
+        for origin in [Origin::TopLeft, Origin::BottomLeft] {
+            let mut scene = Scene::<Vec3>::sized(4, 4);
+            scene.origin = origin;
+            scene.fill_rect(Vec2 { x: 0.0, y: 0.0 }, Vec2 { x: 2.0, y: 1.0 }, white);
+            let row = match origin {
+                Origin::TopLeft => 0,
+                Origin::BottomLeft => 3,
+            };
+            for y in 0..4 {
+                for x in 0..4 {
+                    let filled = y == row && x < 2;
+                    assert_eq!(scene.pixel(x, y), if filled { white } else { black });
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tessellation_splits_only_large_triangles() {
+        let flat = |size: f64| Tri3 {
+            points: [
+                Vec3::new(-size, -size, 0.0),
+                Vec3::new(size, -size, 0.0),
+                Vec3::new(0.0, size, 0.0),
+            ],
+            color: Vec3::new(1.0, 1.0, 1.0),
+            material: 0,
+        };
+        let render = |size, threshold| {
+            let mut scene = Scene::<Vec3>::sized(40, 40);
+            scene.tessellation_threshold = threshold;
+            scene.add_triangle_3d(flat(size));
+            scene.render();
+            scene
+        };
+
+        let large = render(2.0, Some(8.0));
+        assert!(large.stats().triangles_drawn > 1);
+        // splitting doesn't change what's covered
+        assert!(large.output == render(2.0, None).output);
+        assert_eq!(render(0.2, Some(8.0)).stats().triangles_drawn, 1);
+    }
+
+    #[test]
+    fn unchanged_triangles_reuse_their_edges() {
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        let mut scene = Scene::<Vec3>::sized(20, 20);
+        scene.add_triangle(triangle([(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)], white));
+        scene.add_triangle(triangle([(10.0, 10.0), (20.0, 10.0), (10.0, 20.0)], white));
+        scene.render_with_shader(&FlatShader);
+        assert_eq!(scene.stats().edge_cache_hits, 0);
+        let first = scene.output.clone();
+        scene.render_with_shader(&FlatShader);
+        assert_eq!(scene.stats().edge_cache_hits, 2);
+        assert!(scene.output == first);
+
+        scene.add_triangle(triangle([(0.0, 10.0), (5.0, 10.0), (0.0, 15.0)], white));
+        scene.render_with_shader(&FlatShader);
+        assert_eq!(scene.stats().edge_cache_hits, 0);
+    }
+
+    #[test]
+    fn pixel_perfect_quads_cover_each_pixel_once() {
+        let mut scene = Scene::<Vec3>::sized(20, 20);
+        scene.pixel_perfect = true;
+        scene.edge_antialiasing = true;
+        // half transparent, so pixels covered twice come out brighter
+        let quad = |x0: f64, y0: f64, x1: f64, y1: f64| {
+            let half = |points| Tri2 {
+                alpha: 0.5,
+                ..triangle(points, Vec3::new(1.0, 1.0, 1.0))
+            };
+            [
+                half([(x0, y0), (x1, y0), (x1, y1)]),
+                half([(x0, y0), (x1, y1), (x0, y1)]),
+            ]
+        };
+        let edges = [0.0, 4.4, 9.6, 15.2, 20.0];
+        for y in edges.windows(2) {
+            for x in edges.windows(2) {
+                scene.extend_triangles(quad(x[0], y[0], x[1], y[1]));
+            }
+        }
+        scene.render();
+        for pixel in &scene.output {
+            assert_eq!(*pixel, Vec3::new(0.5, 0.5, 0.5));
+        }
+    }
+
+    #[test]
+    fn clipping_a_huge_triangle_covers_the_rect_exactly() {
+        let (min, max) = (Vec2 { x: 0.0, y: 0.0 }, Vec2 { x: 20.0, y: 10.0 });
+        let huge = Tri2 {
+            points: [
+                Vec2 {
+                    x: -1000.0,
+                    y: -1000.0,
+                },
+                Vec2 {
+                    x: 1000.0,
+                    y: -900.0,
+                },
+                Vec2 {
+                    x: -800.0,
+                    y: 1000.0,
+                },
+            ],
+            colors: [
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0),
+            ],
+            alpha: 1.0,
+        };
+        let pieces = huge.clip_to_rect(min, max);
+        assert!(!pieces.is_empty() && pieces.len() <= 5);
+        for piece in &pieces {
+            for point in piece.points {
+                assert!(point.x >= min.x && point.y >= min.y);
+                assert!(point.x <= max.x && point.y <= max.y);
+            }
+        }
+        let area = pieces
+            .iter()
+            .map(|piece| piece.signed_area().abs())
+            .sum::<f64>();
+        assert!((area - 200.0).abs() < 1e-6);
+        // the pieces are colored like the part of the triangle they cover
+        let point = Vec2 { x: 7.3, y: 4.1 };
+        let piece = pieces
+            .iter()
+            .find(|piece| piece.contains(point, 1e-9))
+            .unwrap();
+        assert!((piece.color_at(point) - huge.color_at(point)).length() < 1e-9);
+
+        let outside = triangle([(30.0, 30.0), (40.0, 30.0), (30.0, 40.0)], huge.colors[0]);
+        assert!(outside.clip_to_rect(min, max).is_empty());
+    }
+
+    #[test]
+    fn progressive_rendering_ends_like_a_full_render() {
+        for clear_on_render in [false, true] {
+            let mut full = Scene::random_params(20, 100.0, 4);
+            full.clear_on_render = clear_on_render;
+            full.set_msaa(4);
+            let mut progressive = full.clone();
+            full.render();
+
+            let mut previews = Vec::new();
+            progressive.render_progressive(3, |scene| previews.push(scene.output.clone()));
+            assert_eq!(previews.len(), 3);
+            // the coarsest preview is blocky, so it isn't the final image yet
+            assert!(previews[0] != full.output);
+            assert!(progressive.output == full.output);
+            assert!(progressive.depth == full.depth);
+        }
+    }
+
+    #[test]
+    fn nan_to_zero_only_zeroes_bad_components() {
+        let vector = Vec3::new(1.0, f64::NAN, 3.0);
+        assert!(vector.is_nan() && !vector.is_finite() && !vector.is_infinite());
+        assert_eq!(vector.nan_to_zero(), Vec3::new(1.0, 0.0, 3.0));
+        let vector = Vec3::new(f64::INFINITY, 2.0, 3.0);
+        assert!(vector.is_infinite() && !vector.is_nan());
+        assert_eq!(vector.nan_to_zero(), Vec3::new(0.0, 2.0, 3.0));
+        assert!(Vec3::new(1.0, 2.0, 3.0).is_finite());
+
+        let point = Vec2 {
+            x: f64::NAN,
+            y: -4.0,
+        };
+        assert!(point.is_nan() && !point.is_finite());
+        assert_eq!(point.nan_to_zero(), Vec2 { x: 0.0, y: -4.0 });
+        assert!(
+            Vec2 {
+                x: 0.0,
+                y: f64::NEG_INFINITY
+            }
+            .is_infinite()
+        );
+    }
+
+    #[test]
+    fn instances_draw_separate_copies() {
+        let mut scene = Scene::<Vec3>::sized(40, 40);
+        let cube = Mesh::cube(1.0);
+        scene.draw_instanced(
+            &cube,
+            &[
+                Mat4::translation(Vec3::new(-1.5, 0.0, 0.0)),
+                Mat4::translation(Vec3::new(1.5, 0.0, 0.0)),
+            ],
+        );
+        let covered = |x: usize, y: usize| scene.pixel(x, y) != Vec3::new(0.0, 0.0, 0.0);
+        assert!(covered(9, 20) && covered(31, 20));
+        // there's a gap between them
+        assert!(!covered(20, 20));
+        let count = |xs: std::ops::Range<usize>| {
+            xs.flat_map(|x| (0..40).map(move |y| (x, y)))
+                .filter(|&(x, y)| covered(x, y))
+                .count()
+        };
+        let (left, right) = (count(0..20), count(21..40));
+        assert!(left > 0 && left.abs_diff(right) <= 40, "{left} {right}");
+        // the mesh itself is untouched
+        assert!(cube == Mesh::cube(1.0));
+    }
+
+    #[test]
+    fn render_with_a_generous_deadline_completes() {
+        let mut scene = Scene::random_params(20, 100.0, 9);
+        let deadline = Instant::now() + Duration::from_secs(60);
+        assert!(scene.render_with_deadline(deadline));
+        assert_eq!(scene.stats().triangles_drawn, 20);
+        assert_eq!(scene.stats().triangles_over_deadline, 0);
+
+        // one that's already passed draws nothing
+        let mut late = Scene::random_params(20, 100.0, 9);
+        assert!(!late.render_with_deadline(Instant::now()));
+        assert_eq!(late.stats().triangles_drawn, 0);
+        assert_eq!(late.stats().triangles_over_deadline, 20);
+        assert!(
+            late.output
+                .iter()
+                .all(|pixel| *pixel == Vec3::new(0.0, 0.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn alpha_to_coverage_covers_half_the_samples_at_half_alpha() {
+        let half = Tri2 {
+            alpha: 0.5,
+            ..triangle(
+                [(-10.0, -10.0), (40.0, -10.0), (-10.0, 40.0)],
+                Vec3::new(1.0, 1.0, 1.0),
+            )
+        };
+        let scene = |msaa| {
+            let mut scene = Scene::<Vec3>::sized(8, 8);
+            scene.alpha_to_coverage = true;
+            scene.set_msaa(msaa);
+            scene.extend_triangles([half, half]);
+            scene
+        };
+
+        // both layers cover the same half of the samples, so the second adds nothing
+        let mut stippled = scene(4);
+        stippled.render();
+        for pixel in &stippled.output {
+            assert!((pixel.r() - 0.5).abs() < 1e-9);
+        }
+        let mut drawn = Scene::<Vec3>::sized(8, 8);
+        drawn.alpha_to_coverage = true;
+        drawn.set_msaa(4);
+        drawn.draw_triangle(half);
+        assert!(drawn.output == stippled.output);
+
+        // without separate samples, it blends like usual instead of covering sample 0 or not
+        let mut blended = scene(1);
+        blended.render();
+        let mut late = scene(4);
+        late.render_with_deadline(Instant::now() + Duration::from_secs(60));
+        for pixels in [&blended.output, &late.output] {
+            for pixel in pixels {
+                assert!((pixel.r() - 0.75).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn fitting_fills_the_padded_view() {
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        let mut scene = Scene::<Vec3>::sized(100, 50);
+        scene.add_triangle(triangle([(-3.0, 2.0), (5.0, 2.0), (1.0, 4.0)], white));
+        scene.add_triangle(triangle([(0.0, 1.0), (2.0, 1.0), (1.0, 3.0)], white));
+        scene.fit_to_view(5.0);
+
+        let (top_left, bottom_right) = scene.bounds().unwrap();
+        assert!(top_left.x >= 5.0 - 1e-9 && top_left.y >= 5.0 - 1e-9);
+        assert!(bottom_right.x <= 95.0 + 1e-9 && bottom_right.y <= 45.0 + 1e-9);
+        // 8 wide by 3 tall, so it's the width that fills the view
+        assert!((top_left.x - 5.0).abs() < 1e-9 && (bottom_right.x - 95.0).abs() < 1e-9);
+        assert!(((top_left.y + bottom_right.y) / 2.0 - 25.0).abs() < 1e-9);
+        let size = bottom_right - top_left;
+        assert!((size.x / size.y - 8.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn clearing_to_a_checker_background() {
+        let (black, white) = (Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0));
+        let checker = Texture::new(2, 2, vec![black, white, white, black]).unwrap();
+        let checker_at = |x: usize, y: usize| {
+            if (x + y).is_multiple_of(2) {
+                black
+            } else {
+                white
+            }
+        };
+
+        // stretched, each texel covers a 4 by 4 block
+        let mut scene = Scene::<Vec3>::sized(8, 8);
+        scene.set_background(Some(checker.clone()));
+        scene.clear_color_buffer();
+        for y in 0..8 {
+            for x in 0..8 {
+                assert_eq!(scene.pixel(x, y), checker_at(x / 4, y / 4));
+            }
+        }
+
+        // tiled, a texel per pixel
+        let mut tiled = checker;
+        tiled.address_u = AddressMode::Repeat;
+        tiled.address_v = AddressMode::Repeat;
+        scene.set_background(Some(tiled));
+        scene.clear_on_render = true;
+        scene.render();
+        for y in 0..8 {
+            for x in 0..8 {
+                assert_eq!(scene.pixel(x, y), checker_at(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn turning_the_depth_test_around_clears_the_depth_buffer() {
+        let near = Tri3 {
+            points: [
+                Vec3::new(-3.0, -3.0, 1.0),
+                Vec3::new(3.0, -3.0, 1.0),
+                Vec3::new(0.0, 3.0, 1.0),
+            ],
+            color: Vec3::new(0.0, 1.0, 0.0),
+            material: 0,
+        };
+        // a range with the near value past the far one is reversed too
+        let mut scene = Scene::<Vec3>::sized(20, 20);
+        scene.set_depth_range(1.0, 0.0);
+        assert_eq!(scene.depth(10, 10), f64::NEG_INFINITY);
+        scene.add_triangle_3d(near);
+        scene.render();
+        assert_eq!(scene.pixel(10, 10), Vec3::new(0.0, 1.0, 0.0));
+
+        // reversing it again puts it the usual way around
+        scene.set_reversed_z(true);
+        assert!(scene.reversed_z());
+        assert_eq!(scene.depth(10, 10), f64::INFINITY);
+        // the same direction as before keeps the depths
+        scene.render();
+        let depth = scene.depth(10, 10);
+        scene.set_depth_range(0.5, 0.25);
+        assert_eq!(scene.depth(10, 10), depth);
+    }
+
+    #[test]
+    fn threaded_renders_are_timed_and_reuse_edges() {
+        let mut scene = Scene::<Vec3>::sized(60, 60).with_random_triangles(
+            &mut StdRng::seed_from_u64(3),
+            30,
+            60.0,
+        );
+        scene.clear_on_render = true;
+        scene.render_threaded(4);
+        assert_eq!(scene.stats().edge_cache_hits, 0);
+        assert!(scene.stats().render_time > Duration::ZERO);
+        let first = scene.output.clone();
+        scene.render_threaded(4);
+        assert_eq!(scene.stats().edge_cache_hits, 30);
+        assert!(scene.stats().render_time > Duration::ZERO);
+        assert!(scene.output == first);
+
+        // the serial path shares the same cache
+        scene.render_with_shader(&FlatShader);
+        assert_eq!(scene.stats().edge_cache_hits, 30);
+        assert!(scene.output == first);
+    }
+
+    #[test]
+    fn materials_give_plain_triangles_colored_highlights() {
+        let render = |specular_color| {
+            let mut scene = Scene::<Vec3>::sized(40, 40);
+            scene.lights.push(Light {
+                direction: Vec3::new(0.0, 0.0, 1.0),
+                color: Vec3::new(1.0, 1.0, 1.0),
+            });
+            scene.materials.push(Material {
+                shininess: 32.0,
+                specular_color,
+                ..Material::default()
+            });
+            scene.add_triangle_3d(Tri3 {
+                points: [
+                    Vec3::new(-3.0, -3.0, 0.0),
+                    Vec3::new(3.0, -3.0, 0.0),
+                    Vec3::new(0.0, 3.0, 0.0),
+                ],
+                color: Vec3::new(0.2, 0.2, 0.2),
+                material: 1,
+            });
+            scene.render();
+            scene.pixel(20, 20)
+        };
+        // the light and the camera are both straight in front of the middle
+        let red = render(Vec3::new(1.0, 0.0, 0.0));
+        assert!(red.r() > 1.0, "{red:?}");
+        assert!((red.g() - 0.2 * 1.1).abs() < 1e-9 && (red.b() - 0.2 * 1.1).abs() < 1e-9);
+        // black is no highlights at all
+        assert!((render(Vec3::new(0.0, 0.0, 0.0)) - Vec3::new(0.22, 0.22, 0.22)).length() < 1e-9);
     }
 }
@@ -0,0 +1,94 @@
+//! lighting 3D triangles
+
+use crate::{FrontFace, Tri3, Vec3};
+
+/// A directional light, like the sun.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    /// The direction from surfaces towards the light.
+    pub direction: Vec3,
+    /// The color and brightness of the light.
+    pub color: Vec3,
+}
+
+/// How shiny a surface is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Specular {
+    /// The color of the highlights. Black means no highlights.
+    pub color: Vec3,
+    /// The Phong exponent: the higher it is, the smaller and sharper the highlights.
+    pub shininess: f64,
+}
+
+impl Default for Specular {
+    fn default() -> Self {
+        Self {
+            color: Vec3::new(0.0, 0.0, 0.0),
+            shininess: 32.0,
+        }
+    }
+}
+
+/// The lights of a Scene, copied out for rendering.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Lighting {
+    /// The lights.
+    pub(crate) lights: Vec<Light>,
+    /// The light that reaches every surface from every direction.
+    pub(crate) ambient: Vec3,
+    /// Where the camera is, for the direction highlights are seen from.
+    pub(crate) camera_position: Vec3,
+}
+
+impl Lighting {
+    /// Light a point at `position` with the surface `normal` and the color `base` with the
+    /// Phong model: ambient + diffuse + specular.
+    pub(crate) fn shade(
+        &self,
+        base: Vec3,
+        normal: Vec3,
+        position: Vec3,
+        specular: Specular,
+    ) -> Vec3 {
+        let normal = normal.normalize();
+        let view = (self.camera_position - position).normalize();
+
+        let mut color = base * self.ambient;
+        for light in &self.lights {
+            let to_light = light.direction.normalize();
+            let diffuse = normal.dot(to_light).max(0.0);
+            if diffuse <= 0.0 {
+                continue;
+            }
+            color += base * light.color * diffuse;
+
+            let reflected = (to_light * -1.0).reflect(normal);
+            let highlight = reflected.dot(view).max(0.0).powf(specular.shininess);
+            color += specular.color * light.color * highlight;
+        }
+        color
+    }
+}
+
+/// A 3D triangle with everything needed to light it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct LitTri {
+    /// The triangle.
+    pub(crate) triangle: Tri3,
+    /// The normal at each point.
+    pub(crate) normals: [Vec3; 3],
+    /// How shiny the triangle is.
+    pub(crate) specular: Specular,
+}
+
+impl LitTri {
+    /// A triangle with the normal of its front face (decided by `front_face`) at every point and
+    /// no highlights.
+    pub(crate) fn new(triangle: Tri3, front_face: FrontFace) -> Self {
+        Self {
+            triangle,
+            normals: [triangle.front_normal(front_face); 3],
+            specular: Specular::default(),
+        }
+    }
+}
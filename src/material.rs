@@ -0,0 +1,33 @@
+//! materials shared between triangles
+
+use crate::Vec3;
+
+/// How a 3D triangle looks, shared between all the triangles that use it (see
+/// [`Scene::materials`](crate::Scene::materials)).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Material {
+    /// The color, multiplied with the triangle's own color.
+    pub base_color: Vec3,
+    /// The Phong exponent for highlights in [`Material::specular_color`], or 0.0 to keep the
+    /// triangle's own highlights (which only triangles from a [`Mesh`](crate::Mesh) have).
+    pub shininess: f64,
+    /// The color of the highlights when [`Material::shininess`] is above 0.0. Black means no
+    /// highlights.
+    pub specular_color: Vec3,
+    /// The index of the texture (in [`Scene::textures`](crate::Scene::textures)) multiplied with
+    /// the color, if there is one. Each triangle has its first point at UV (0, 0), its second at
+    /// (1, 0), and its third at (0, 1).
+    pub texture: Option<usize>,
+}
+
+impl Default for Material {
+    /// White, without highlights or a texture, so triangles keep their own color.
+    fn default() -> Self {
+        Self {
+            base_color: Vec3::new(1.0, 1.0, 1.0),
+            shininess: 0.0,
+            specular_color: Vec3::new(0.0, 0.0, 0.0),
+            texture: None,
+        }
+    }
+}
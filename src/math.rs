@@ -0,0 +1,363 @@
+//! matrices
+
+use crate::{Vec2, Vec3};
+use std::ops::Mul;
+
+/// How small a pivot can be, relative to the largest value in the matrix, before the matrix is
+/// treated as singular.
+const SINGULAR_EPSILON: f64 = 1e-12;
+
+/// Reduce `rows` to upper triangular form with Gaussian elimination and partial pivoting,
+/// applying the same row operations to `other`. Returns the determinant, or `None` if the matrix
+/// is singular (or too close to it for the result to mean anything).
+fn eliminate<const N: usize>(rows: &mut [[f64; N]; N], other: &mut [[f64; N]; N]) -> Option<f64> {
+    let largest = rows
+        .iter()
+        .flatten()
+        .fold(0.0, |largest: f64, value| largest.max(value.abs()));
+    if !largest.is_finite() || largest == 0.0 {
+        return None;
+    }
+    let mut determinant = 1.0;
+    for column in 0..N {
+        let pivot =
+            (column..N).max_by(|&a, &b| rows[a][column].abs().total_cmp(&rows[b][column].abs()))?;
+        if rows[pivot][column].abs() <= SINGULAR_EPSILON * largest {
+            return None;
+        }
+        if pivot != column {
+            rows.swap(pivot, column);
+            other.swap(pivot, column);
+            determinant = -determinant;
+        }
+        determinant *= rows[column][column];
+        for row in column + 1..N {
+            let factor = rows[row][column] / rows[column][column];
+            for i in 0..N {
+                rows[row][i] -= factor * rows[column][i];
+                other[row][i] -= factor * other[column][i];
+            }
+        }
+    }
+    Some(determinant)
+}
+
+/// The determinant of a square matrix, 0.0 if it's singular.
+fn determinant<const N: usize>(mut rows: [[f64; N]; N]) -> f64 {
+    eliminate(&mut rows, &mut [[0.0; N]; N]).unwrap_or(0.0)
+}
+
+/// The inverse of a square matrix with Gauss-Jordan elimination, or `None` if it's singular.
+fn inverse<const N: usize>(mut rows: [[f64; N]; N]) -> Option<[[f64; N]; N]> {
+    let mut inverse = [[0.0; N]; N];
+    for (i, row) in inverse.iter_mut().enumerate() {
+        row[i] = 1.0;
+    }
+    eliminate(&mut rows, &mut inverse)?;
+    // back substitution, from the bottom row up
+    for column in (0..N).rev() {
+        let pivot = rows[column][column];
+        let solved = inverse[column].map(|value| value / pivot);
+        inverse[column] = solved;
+        for row in 0..column {
+            let factor = rows[row][column];
+            for (value, solved) in inverse[row].iter_mut().zip(solved) {
+                (*value) -= factor * solved;
+            }
+        }
+    }
+    Some(inverse)
+}
+
+/// A 3x3 matrix, e.g. for transforming directions and normals.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat3 {
+    /// The rows of the matrix.
+    pub rows: [[f64; 3]; 3],
+}
+
+impl Default for Mat3 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Mul for Mat3 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        let mut rows = [[0.0; 3]; 3];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                (*value) = (0..3).map(|k| self.rows[i][k] * rhs.rows[k][j]).sum();
+            }
+        }
+        Self { rows }
+    }
+}
+
+impl Mat3 {
+    /// The identity matrix.
+    pub const fn identity() -> Self {
+        Self {
+            rows: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        }
+    }
+    /// A 2D translation by `offset`, for transforming points with
+    /// [`Mat3::transform_point_2d`].
+    pub fn translation_2d(offset: Vec2) -> Self {
+        let mut matrix = Self::identity();
+        matrix.rows[0][2] = offset.x;
+        matrix.rows[1][2] = offset.y;
+        matrix
+    }
+    /// A 2D scale by `factor` along each axis, for transforming points with
+    /// [`Mat3::transform_point_2d`].
+    pub fn scale_2d(factor: Vec2) -> Self {
+        let mut matrix = Self::identity();
+        matrix.rows[0][0] = factor.x;
+        matrix.rows[1][1] = factor.y;
+        matrix
+    }
+    /// Transform a 2D point in homogeneous coordinates, dividing by the resulting W.
+    pub fn transform_point_2d(self, point: Vec2) -> Vec2 {
+        let [x, y, w] = self
+            .rows
+            .map(|row| row[0] * point.x + row[1] * point.y + row[2]);
+        Vec2 { x: x / w, y: y / w }
+    }
+    /// Transform a vector.
+    pub fn transform(self, vector: Vec3) -> Vec3 {
+        let [x, y, z] = self
+            .rows
+            .map(|row| row[0] * vector.x() + row[1] * vector.y() + row[2] * vector.z());
+        Vec3::new(x, y, z)
+    }
+    /// The determinant: how much the matrix scales volumes by, negative if it mirrors them.
+    pub fn determinant(self) -> f64 {
+        determinant(self.rows)
+    }
+    /// The inverse, which undoes this matrix, or `None` if it's singular (it flattens space, so
+    /// it can't be undone).
+    pub fn inverse(self) -> Option<Self> {
+        inverse(self.rows).map(|rows| Self { rows })
+    }
+    /// The transpose, with the rows and columns swapped.
+    pub fn transpose(self) -> Self {
+        Self {
+            rows: [0, 1, 2].map(|i| self.rows.map(|row| row[i])),
+        }
+    }
+}
+
+/// A 4x4 matrix for transforming points in homogeneous coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    /// The rows of the matrix.
+    pub rows: [[f64; 4]; 4],
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        let mut rows = [[0.0; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                (*value) = (0..4).map(|k| self.rows[i][k] * rhs.rows[k][j]).sum();
+            }
+        }
+        Self { rows }
+    }
+}
+
+impl Mat4 {
+    /// The identity matrix.
+    pub const fn identity() -> Self {
+        Self {
+            rows: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+    /// A translation by `offset`.
+    pub fn translation(offset: Vec3) -> Self {
+        let mut matrix = Self::identity();
+        matrix.rows[0][3] = offset.x();
+        matrix.rows[1][3] = offset.y();
+        matrix.rows[2][3] = offset.z();
+        matrix
+    }
+    /// A scale by `factor` along each axis.
+    pub fn scale(factor: Vec3) -> Self {
+        let mut matrix = Self::identity();
+        matrix.rows[0][0] = factor.x();
+        matrix.rows[1][1] = factor.y();
+        matrix.rows[2][2] = factor.z();
+        matrix
+    }
+    /// A rotation of `angle` radians around the Y axis (counter-clockwise looking down it).
+    pub fn rotation_y(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        let mut matrix = Self::identity();
+        matrix.rows[0][0] = cos;
+        matrix.rows[0][2] = sin;
+        matrix.rows[2][0] = -sin;
+        matrix.rows[2][2] = cos;
+        matrix
+    }
+    /// A right-handed perspective projection looking down -Z, with a vertical field of view of
+    /// `fov_y` radians. Points on the `near` plane get a depth of `depth_near` and points on the
+    /// `far` plane get `depth_far`.
+    pub fn perspective(
+        fov_y: f64,
+        aspect: f64,
+        near: f64,
+        far: f64,
+        depth_near: f64,
+        depth_far: f64,
+    ) -> Self {
+        let focal_length = 1.0 / (fov_y / 2.0).tan();
+        // depth = b / distance - a, solved for the near and far planes
+        let b = (depth_near - depth_far) * near * far / (far - near);
+        let a = b / near - depth_near;
+
+        Self {
+            rows: [
+                [focal_length / aspect, 0.0, 0.0, 0.0],
+                [0.0, focal_length, 0.0, 0.0],
+                [0.0, 0.0, a, b],
+                [0.0, 0.0, -1.0, 0.0],
+            ],
+        }
+    }
+    /// The determinant: how much the matrix scales volumes by, negative if it mirrors them.
+    pub fn determinant(self) -> f64 {
+        determinant(self.rows)
+    }
+    /// The inverse, which undoes this matrix (e.g. turning screen coordinates back into world
+    /// coordinates with an inverse view-projection), or `None` if it's singular.
+    pub fn inverse(self) -> Option<Self> {
+        inverse(self.rows).map(|rows| Self { rows })
+    }
+    /// The matrix for transforming normals along with points: the inverse transpose of the
+    /// top-left 3x3, so normals stay perpendicular to surfaces under non-uniform scales. If that's
+    /// singular, it's just the top-left 3x3.
+    pub fn normal_matrix(self) -> Mat3 {
+        let linear = Mat3 {
+            rows: [0, 1, 2].map(|i| [self.rows[i][0], self.rows[i][1], self.rows[i][2]]),
+        };
+        linear
+            .inverse()
+            .map_or(linear, |inverse| inverse.transpose())
+    }
+    /// Transform a point in homogeneous coordinates.
+    pub fn transform(self, point: [f64; 4]) -> [f64; 4] {
+        self.rows.map(|row| (0..4).map(|i| row[i] * point[i]).sum())
+    }
+    /// Transform a point, dividing by the resulting W.
+    pub fn transform_point(self, point: Vec3) -> Vec3 {
+        let [x, y, z, w] = self.transform([point.x(), point.y(), point.z(), 1.0]);
+        Vec3::new(x / w, y / w, z / w)
+    }
+    /// Transform every point in `points` into the same place in `out`, dividing by W, like
+    /// [`Mat4::transform_point`]. This is a tight loop with no allocation that the compiler can
+    /// autovectorize, for transforming many points at once.
+    ///
+    /// # Panics
+    /// If `points` and `out` have different lengths.
+    pub fn transform_points(&self, points: &[Vec3], out: &mut [Vec3]) {
+        assert_eq!(
+            points.len(),
+            out.len(),
+            "`points` and `out` must have the same length"
+        );
+        let [r0, r1, r2, r3] = self.rows;
+        for (point, out) in points.iter().zip(out.iter_mut()) {
+            let (x, y, z) = (point.x(), point.y(), point.z());
+            let w = r3[0] * x + r3[1] * y + r3[2] * z + r3[3];
+            (*out) = Vec3::new(
+                (r0[0] * x + r0[1] * y + r0[2] * z + r0[3]) / w,
+                (r1[0] * x + r1[1] * y + r1[2] * z + r1[3]) / w,
+                (r2[0] * x + r2[1] * y + r2[2] * z + r2[3]) / w,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn transform_points_translates_each_point() {
+        let offset = Vec3::new(1.0, -2.0, 0.5);
+        let points = [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 2.0, 3.0),
+            Vec3::new(-4.0, 5.0, -6.0),
+        ];
+        let mut out = [Vec3::new(0.0, 0.0, 0.0); 3];
+        let matrix = Mat4::translation(offset);
+        matrix.transform_points(&points, &mut out);
+        for (point, out) in points.iter().zip(out) {
+            assert_eq!(out, *point + offset);
+            assert_eq!(out, matrix.transform_point(*point));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn transform_points_checks_lengths() {
+        let mut out = [Vec3::new(0.0, 0.0, 0.0); 2];
+        Mat4::identity().transform_points(&[Vec3::new(0.0, 0.0, 0.0)], &mut out);
+    }
+
+    #[test]
+    fn inverses_undo_random_transforms() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut random = || rng.random_range(-2.0..2.0);
+        let transform = Mat4::translation(Vec3::new(random(), random(), random()))
+            * Mat4::rotation_y(random())
+            * Mat4::scale(Vec3::new(1.5 + random(), 2.5, 0.5 + random().abs()));
+        let product = transform * transform.inverse().unwrap();
+        for (row, identity) in product.rows.iter().zip(Mat4::identity().rows) {
+            for (value, expected) in row.iter().zip(identity) {
+                assert!((value - expected).abs() < 1e-9);
+            }
+        }
+
+        let mut rows = [[0.0; 3]; 3];
+        for value in rows.iter_mut().flatten() {
+            (*value) = random();
+        }
+        let matrix = Mat3 { rows };
+        assert!(matrix.determinant().abs() > 1e-6);
+        let product = matrix * matrix.inverse().unwrap();
+        for (row, identity) in product.rows.iter().zip(Mat3::identity().rows) {
+            for (value, expected) in row.iter().zip(identity) {
+                assert!((value - expected).abs() < 1e-9);
+            }
+        }
+
+        // a scale by zero flattens everything, so there's no way back
+        let flat = Mat4::scale(Vec3::new(1.0, 0.0, 1.0));
+        assert_eq!(flat.determinant(), 0.0);
+        assert_eq!(flat.inverse(), None);
+        assert_eq!(
+            Mat3 {
+                rows: [[1.0, 2.0, 3.0]; 3]
+            }
+            .inverse(),
+            None
+        );
+    }
+}
@@ -0,0 +1,540 @@
+//! 3D triangles and meshes
+
+use crate::lighting::LitTri;
+use crate::{Camera, HashableVec3, Specular, Vec3};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+/// Which winding order of a triangle's points, seen from the camera, marks its front face.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrontFace {
+    /// Counter-clockwise points face the camera.
+    #[default]
+    Ccw,
+    /// Clockwise points face the camera.
+    Cw,
+}
+
+/// A 3D triangle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tri3 {
+    /// The points of the triangle, counter-clockwise when seen from the front (unless the
+    /// [`FrontFace`] says otherwise).
+    pub points: [Vec3; 3],
+    /// The color.
+    pub color: Vec3,
+    /// The index of the material (in [`Scene::materials`](crate::Scene::materials)). 0 is the
+    /// default material, which leaves the triangle as it is unless it's changed.
+    pub material: usize,
+}
+
+impl Tri3 {
+    /// The unit normal of the front face of the triangle.
+    pub fn normal(self) -> Vec3 {
+        (self.points[1] - self.points[0])
+            .cross(self.points[2] - self.points[0])
+            .normalize()
+    }
+    /// The unit normal of the front face of the triangle, with the front face decided by
+    /// `front_face`.
+    pub fn front_normal(self, front_face: FrontFace) -> Vec3 {
+        match front_face {
+            FrontFace::Ccw => self.normal(),
+            FrontFace::Cw => self.normal() * -1.0,
+        }
+    }
+    /// The average of the points of the triangle.
+    pub fn centroid(self) -> Vec3 {
+        (self.points[0] + self.points[1] + self.points[2]) * (1.0 / 3.0)
+    }
+}
+
+/// An indexed triangle mesh.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mesh {
+    /// The positions of the vertices.
+    pub vertices: Vec<Vec3>,
+    /// The normal of each vertex.
+    pub normals: Vec<Vec3>,
+    /// The vertex indices of each triangle, counter-clockwise when seen from the front.
+    pub indices: Vec<[usize; 3]>,
+    /// The color of the mesh.
+    pub color: Vec3,
+    /// The highlights of the mesh, when the Scene has lights.
+    pub specular: Specular,
+    /// Lower-detail versions of the mesh, from most to least detailed, used when it's small on
+    /// the screen (see [`Scene::lod_thresholds`](crate::Scene::lod_thresholds)).
+    pub lods: Vec<Mesh>,
+}
+
+impl Mesh {
+    /// The number of triangles in the mesh.
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len()
+    }
+    /// The mesh with `lods` as its lower-detail versions, from most to least detailed.
+    pub fn with_lods(self, lods: Vec<Mesh>) -> Self {
+        Self { lods, ..self }
+    }
+    /// The smallest box containing every vertex, as (minimum, maximum) corners. `None` if there
+    /// are no vertices.
+    pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        let first = *self.vertices.first()?;
+        Some(self.vertices.iter().fold((first, first), |(min, max), v| {
+            (
+                Vec3::new(min.x().min(v.x()), min.y().min(v.y()), min.z().min(v.z())),
+                Vec3::new(max.x().max(v.x()), max.y().max(v.y()), max.z().max(v.z())),
+            )
+        }))
+    }
+    /// Replace the normals with smooth ones: the average of the normals of the faces around
+    /// each vertex, weighted by the angle of the face at the vertex. Vertices at the same
+    /// position share a normal even if they aren't welded together.
+    pub fn compute_smooth_normals(&mut self) {
+        let mut sums = HashMap::<HashableVec3, Vec3>::new();
+        for indices in &self.indices {
+            let points = indices.map(|i| self.vertices[i]);
+            let face_normal = (points[1] - points[0])
+                .cross(points[2] - points[0])
+                .normalize();
+            for corner in 0..3 {
+                let point = points[corner];
+                let to_next = (points[(corner + 1) % 3] - point).normalize();
+                let to_previous = (points[(corner + 2) % 3] - point).normalize();
+                let angle = to_next.dot(to_previous).clamp(-1.0, 1.0).acos();
+                *sums.entry(point.into()).or_insert(Vec3::new(0.0, 0.0, 0.0)) +=
+                    face_normal * angle;
+            }
+        }
+
+        self.normals = self
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(i, &vertex)| match sums.get(&vertex.into()) {
+                Some(sum) => sum.normalize(),
+                // vertices that aren't part of any face keep their normal
+                None => self
+                    .normals
+                    .get(i)
+                    .copied()
+                    .unwrap_or(Vec3::new(0.0, 0.0, 0.0)),
+            })
+            .collect();
+    }
+    /// The edges between a triangle facing the camera and one facing away from it, which make
+    /// up the outline of the mesh. Edges are matched by the positions of their ends, so vertices
+    /// don't need to be welded together.
+    pub fn silhouette_edges(&self, camera: &Camera) -> Vec<(Vec3, Vec3)> {
+        // (ends, any front face, any back face) for each edge, in the order they're found
+        let mut edges = Vec::<((Vec3, Vec3), bool, bool)>::new();
+        let mut lookup = HashMap::<[[u64; 3]; 2], usize>::new();
+        for triangle in self.triangles() {
+            let front = triangle.normal().dot(triangle.centroid() - camera.position) < 0.0;
+            for corner in 0..3 {
+                let (a, b) = (triangle.points[corner], triangle.points[(corner + 1) % 3]);
+                let key = if a.to_bits() <= b.to_bits() {
+                    [a.to_bits(), b.to_bits()]
+                } else {
+                    [b.to_bits(), a.to_bits()]
+                };
+                let index = *lookup.entry(key).or_insert_with(|| {
+                    edges.push(((a, b), false, false));
+                    edges.len() - 1
+                });
+                let (_, any_front, any_back) = &mut edges[index];
+                if front {
+                    *any_front = true;
+                } else {
+                    *any_back = true;
+                }
+            }
+        }
+
+        edges
+            .into_iter()
+            .filter(|&(_, any_front, any_back)| any_front && any_back)
+            .map(|(ends, _, _)| ends)
+            .collect()
+    }
+    /// The triangles of the mesh.
+    pub fn triangles(&self) -> impl Iterator<Item = Tri3> + '_ {
+        self.indices.iter().map(|indices| Tri3 {
+            points: indices.map(|i| self.vertices[i]),
+            color: self.color,
+            material: 0,
+        })
+    }
+    /// The triangles of the mesh with their vertex normals, falling back to the normal of the
+    /// front face (decided by `front_face`) if there isn't a normal for every vertex.
+    pub(crate) fn lit_triangles(
+        &self,
+        front_face: FrontFace,
+    ) -> impl Iterator<Item = LitTri> + '_ {
+        self.triangles()
+            .zip(&self.indices)
+            .map(move |(triangle, indices)| LitTri {
+                normals: if self.normals.len() == self.vertices.len() {
+                    indices.map(|i| self.normals[i])
+                } else {
+                    [triangle.front_normal(front_face); 3]
+                },
+                specular: self.specular,
+                triangle,
+            })
+    }
+    /// A cube centered on the origin with sides of length `size`. Each face has its own vertices
+    /// so the normals are flat, which makes 24 vertices and 12 triangles.
+    pub fn cube(size: f64) -> Self {
+        let half = size / 2.0;
+        let mut mesh = Self {
+            vertices: Vec::with_capacity(24),
+            normals: Vec::with_capacity(24),
+            indices: Vec::with_capacity(12),
+            color: Vec3::new(1.0, 1.0, 1.0),
+            specular: Specular::default(),
+            lods: Vec::new(),
+        };
+
+        for axis in 0..3 {
+            for sign in [1.0, -1.0] {
+                let normal = axis_vector(axis, sign);
+                // two axes along the face, with u cross v pointing along the normal
+                let u = axis_vector((axis + 1) % 3, sign);
+                let v = axis_vector((axis + 2) % 3, 1.0);
+
+                let first = mesh.vertices.len();
+                for (a, b) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                    mesh.vertices.push((normal + u * a + v * b) * half);
+                    mesh.normals.push(normal);
+                }
+                mesh.indices.push([first, first + 1, first + 2]);
+                mesh.indices.push([first, first + 2, first + 3]);
+            }
+        }
+
+        mesh
+    }
+    /// A sphere centered on the origin made of `rings` horizontal bands and `sectors` vertical
+    /// slices. The bands touching the poles are made of single triangles, so there are
+    /// `sectors * (2 * rings - 2)` triangles in total.
+    pub fn uv_sphere(rings: usize, sectors: usize, radius: f64) -> Self {
+        let rings = rings.max(2);
+        let sectors = sectors.max(3);
+
+        let mut mesh = Self {
+            vertices: Vec::with_capacity((rings + 1) * (sectors + 1)),
+            normals: Vec::with_capacity((rings + 1) * (sectors + 1)),
+            indices: Vec::with_capacity(sectors * (2 * rings - 2)),
+            color: Vec3::new(1.0, 1.0, 1.0),
+            specular: Specular::default(),
+            lods: Vec::new(),
+        };
+
+        for ring in 0..=rings {
+            let polar = PI * ring as f64 / rings as f64;
+            for sector in 0..=sectors {
+                let azimuth = 2.0 * PI * sector as f64 / sectors as f64;
+                let normal = Vec3::new(
+                    polar.sin() * azimuth.cos(),
+                    polar.cos(),
+                    -polar.sin() * azimuth.sin(),
+                );
+                mesh.vertices.push(normal * radius);
+                mesh.normals.push(normal);
+            }
+        }
+
+        for ring in 0..rings {
+            for sector in 0..sectors {
+                let top = ring * (sectors + 1) + sector;
+                let bottom = top + sectors + 1;
+                if ring != 0 {
+                    mesh.indices.push([top, bottom, top + 1]);
+                }
+                if ring != rings - 1 {
+                    mesh.indices.push([top + 1, bottom, bottom + 1]);
+                }
+            }
+        }
+
+        mesh
+    }
+}
+
+/// Weld a triangle soup into an indexed mesh, merging points within `epsilon` of each other
+/// into one vertex (the first of them) with a spatial hash. Triangles that collapse because two
+/// of their points were merged are dropped. The mesh gets smooth normals and the color of the
+/// first triangle; materials are ignored.
+pub fn weld_triangles(tris: &[Tri3], epsilon: f64) -> Mesh {
+    // points are only compared with the points in the cells around theirs
+    let cell_size = if epsilon > 0.0 { epsilon } else { 1.0 };
+    let cell = |point: Vec3| {
+        [point.x(), point.y(), point.z()].map(|value| (value / cell_size).floor() as i64)
+    };
+
+    let mut grid = HashMap::<[i64; 3], Vec<usize>>::new();
+    let mut vertices = Vec::<Vec3>::new();
+    let mut weld = |point: Vec3| {
+        let [x, y, z] = cell(point);
+        for neighbor in (-1..=1).flat_map(|dx| {
+            (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| [x + dx, y + dy, z + dz]))
+        }) {
+            let Some(indices) = grid.get(&neighbor) else {
+                continue;
+            };
+            if let Some(&index) = indices
+                .iter()
+                .find(|&&index| (vertices[index] - point).length() <= epsilon)
+            {
+                return index;
+            }
+        }
+        vertices.push(point);
+        grid.entry([x, y, z]).or_default().push(vertices.len() - 1);
+        vertices.len() - 1
+    };
+
+    let indices = tris
+        .iter()
+        .map(|triangle| triangle.points.map(&mut weld))
+        .filter(|&[a, b, c]| a != b && b != c && c != a)
+        .collect();
+    let mut mesh = Mesh {
+        vertices,
+        normals: Vec::new(),
+        indices,
+        color: tris
+            .first()
+            .map_or(Vec3::new(1.0, 1.0, 1.0), |triangle| triangle.color),
+        specular: Specular::default(),
+        lods: Vec::new(),
+    };
+    mesh.compute_smooth_normals();
+    mesh
+}
+
+/// Expand a triangle fan around `points[0]` into triangles, which keep the winding of the fan.
+/// There are `points.len() - 2` triangles, or none with fewer than 3 points.
+pub fn triangle_fan(points: &[Vec3], color: Vec3) -> Vec<Tri3> {
+    let Some((&center, rest)) = points.split_first() else {
+        return Vec::new();
+    };
+    rest.windows(2)
+        .map(|pair| Tri3 {
+            points: [center, pair[0], pair[1]],
+            color,
+            material: 0,
+        })
+        .collect()
+}
+
+/// Expand a triangle strip into triangles. Every other triangle has its first two points
+/// swapped so they all keep the winding of the first one. There are `points.len() - 2`
+/// triangles, or none with fewer than 3 points.
+pub fn triangle_strip(points: &[Vec3], color: Vec3) -> Vec<Tri3> {
+    points
+        .windows(3)
+        .enumerate()
+        .map(|(i, window)| Tri3 {
+            points: if i % 2 == 0 {
+                [window[0], window[1], window[2]]
+            } else {
+                [window[1], window[0], window[2]]
+            },
+            color,
+            material: 0,
+        })
+        .collect()
+}
+
+/// Where the ray from `origin` along `dir` hits `tri`, with the Möller-Trumbore algorithm, as
+/// the distance along the ray in multiples of `dir` (so the real distance if `dir` is a unit
+/// vector). Both faces can be hit, but not points behind the origin. `None` if it misses, or the
+/// ray runs along the triangle's plane.
+pub fn ray_triangle_intersect(origin: Vec3, dir: Vec3, tri: &Tri3) -> Option<f64> {
+    /// How close to parallel to the plane the ray can be, and how close to the origin a hit can
+    /// be, before it doesn't count.
+    const EPSILON: f64 = 1e-12;
+
+    let [a, b, c] = tri.points;
+    let (ab, ac) = (b - a, c - a);
+    let p = dir.cross(ac);
+    let determinant = ab.dot(p);
+    if determinant.abs() < EPSILON {
+        return None;
+    }
+    let inverse = 1.0 / determinant;
+    let to_origin = origin - a;
+    let u = to_origin.dot(p) * inverse;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = to_origin.cross(ab);
+    let v = dir.dot(q) * inverse;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = ac.dot(q) * inverse;
+    (t > EPSILON).then_some(t)
+}
+
+/// A unit vector along the X (0), Y (1), or Z (2) axis, multiplied by `sign`.
+fn axis_vector(axis: usize, sign: f64) -> Vec3 {
+    match axis {
+        0 => Vec3::new(sign, 0.0, 0.0),
+        1 => Vec3::new(0.0, sign, 0.0),
+        _ => Vec3::new(0.0, 0.0, sign),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primitive_triangle_counts() {
+        assert_eq!(Mesh::cube(2.0).triangle_count(), 12);
+        for (rings, sectors) in [(2, 3), (8, 16), (5, 7)] {
+            let sphere = Mesh::uv_sphere(rings, sectors, 1.5);
+            assert_eq!(sphere.triangle_count(), sectors * (2 * rings - 2));
+            assert!(
+                sphere
+                    .vertices
+                    .iter()
+                    .all(|vertex| (vertex.length() - 1.5).abs() < 1e-9)
+            );
+        }
+    }
+
+    #[test]
+    fn fans_and_strips_keep_the_winding() {
+        let color = Vec3::new(1.0, 1.0, 1.0);
+        // a zigzag in the XY plane, so every triangle faces +Z if the winding is kept
+        let points = (0..5)
+            .map(|i| Vec3::new((i / 2) as f64, (i % 2) as f64, 0.0))
+            .collect::<Vec<Vec3>>();
+        for count in [4, 5] {
+            let strip = triangle_strip(&points[..count], color);
+            assert_eq!(strip.len(), count - 2);
+            let first = strip[0].normal();
+            assert!(
+                strip
+                    .iter()
+                    .all(|triangle| triangle.normal().dot(first) > 0.99)
+            );
+
+            let ring = (0..count)
+                .map(|i| {
+                    let angle = i as f64 * 0.5;
+                    Vec3::new(angle.cos(), angle.sin(), 0.0)
+                })
+                .collect::<Vec<Vec3>>();
+            let fan = triangle_fan(&ring, color);
+            assert_eq!(fan.len(), count - 2);
+            assert!(fan.iter().all(|triangle| triangle.normal().z() > 0.99));
+        }
+        assert!(triangle_fan(&points[..2], color).is_empty());
+        assert!(triangle_strip(&points[..2], color).is_empty());
+    }
+
+    #[test]
+    fn smooth_cube_normals_point_diagonally() {
+        let mut cube = Mesh::cube(2.0);
+        cube.compute_smooth_normals();
+        // the faces have their own vertices, but each corner is shared by three faces
+        for (vertex, normal) in cube.vertices.iter().zip(&cube.normals) {
+            let diagonal = vertex.normalize();
+            assert!(
+                (*normal - diagonal).length() < 1e-9,
+                "{vertex:?} {normal:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn cube_silhouettes() {
+        let cube = Mesh::cube(2.0);
+        // seen from a corner the outline is a hexagon
+        let corner = Camera::look_at(
+            Vec3::new(5.0, 5.0, 5.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        assert_eq!(cube.silhouette_edges(&corner).len(), 6);
+        // seen straight on it's the square of the front face
+        let front = Camera::default();
+        let edges = cube.silhouette_edges(&front);
+        assert_eq!(edges.len(), 4);
+        assert!(edges.iter().all(|(a, b)| a.z() == 1.0 && b.z() == 1.0));
+    }
+
+    #[test]
+    fn triangles_sharing_an_edge_weld() {
+        let color = Vec3::new(1.0, 1.0, 1.0);
+        let tris = [
+            Tri3 {
+                points: [
+                    Vec3::new(0.0, 0.0, 0.0),
+                    Vec3::new(1.0, 0.0, 0.0),
+                    Vec3::new(0.0, 1.0, 0.0),
+                ],
+                color,
+                material: 0,
+            },
+            Tri3 {
+                // the shared edge is a little off, but within epsilon
+                points: [
+                    Vec3::new(1.0, 1e-7, 0.0),
+                    Vec3::new(1.0, 1.0, 0.0),
+                    Vec3::new(-1e-7, 1.0, 0.0),
+                ],
+                color,
+                material: 0,
+            },
+        ];
+        let mesh = weld_triangles(&tris, 1e-5);
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(mesh.indices[1][0], mesh.indices[0][1]);
+        assert_eq!(mesh.indices[1][2], mesh.indices[0][2]);
+        assert_eq!(mesh.normals.len(), 4);
+        // without welding nothing is shared
+        assert_eq!(weld_triangles(&tris, 0.0).vertices.len(), 6);
+    }
+
+    #[test]
+    fn rays_hit_triangles_at_the_right_distance() {
+        let tri = Tri3 {
+            points: [
+                Vec3::new(-1.0, -1.0, -2.0),
+                Vec3::new(1.0, -1.0, -2.0),
+                Vec3::new(0.0, 1.0, -2.0),
+            ],
+            color: Vec3::new(1.0, 1.0, 1.0),
+            material: 0,
+        };
+        let origin = Vec3::new(0.0, 0.0, 3.0);
+        let t = ray_triangle_intersect(origin, Vec3::new(0.0, 0.0, -1.0), &tri).unwrap();
+        assert!((t - 5.0).abs() < 1e-12);
+        // not a unit vector, so it's in multiples of it
+        let t = ray_triangle_intersect(origin, Vec3::new(0.0, 0.0, -2.0), &tri).unwrap();
+        assert!((t - 2.5).abs() < 1e-12);
+
+        // beside it, behind the origin, and along its plane
+        let beside = Vec3::new(2.0, 0.0, 3.0);
+        assert_eq!(
+            ray_triangle_intersect(beside, Vec3::new(0.0, 0.0, -1.0), &tri),
+            None
+        );
+        assert_eq!(
+            ray_triangle_intersect(origin, Vec3::new(0.0, 0.0, 1.0), &tri),
+            None
+        );
+        let on_plane = Vec3::new(-5.0, 0.0, -2.0);
+        assert_eq!(
+            ray_triangle_intersect(on_plane, Vec3::new(1.0, 0.0, 0.0), &tri),
+            None
+        );
+    }
+}
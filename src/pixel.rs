@@ -0,0 +1,78 @@
+//! the types pixels can be stored as
+
+use crate::Vec3;
+
+/// A type the pixels of a Scene's output can be stored as. Rendering works in [`Vec3`]s, which
+/// are converted to and from the stored type.
+pub trait Pixel: Copy + PartialEq + std::fmt::Debug + Send + Sync {
+    /// Convert a color to a pixel.
+    fn from_vec3(color: Vec3) -> Self;
+    /// Convert the pixel to a color.
+    fn to_vec3(self) -> Vec3;
+}
+
+/// Full f64 precision, with no clamping, for HDR.
+impl Pixel for Vec3 {
+    fn from_vec3(color: Vec3) -> Self {
+        color
+    }
+    fn to_vec3(self) -> Vec3 {
+        self
+    }
+}
+
+/// f32 precision, with no clamping, for HDR with half the memory.
+impl Pixel for [f32; 3] {
+    fn from_vec3(color: Vec3) -> Self {
+        [color.r() as f32, color.g() as f32, color.b() as f32]
+    }
+    fn to_vec3(self) -> Vec3 {
+        Vec3::new(self[0] as f64, self[1] as f64, self[2] as f64)
+    }
+}
+
+/// 8-bit RGB, clamped to 0.0 to 1.0, with an eighth of the memory.
+impl Pixel for [u8; 3] {
+    fn from_vec3(color: Vec3) -> Self {
+        color.to_rgb8()
+    }
+    fn to_vec3(self) -> Vec3 {
+        Vec3::new(self[0] as f64, self[1] as f64, self[2] as f64) * (1.0 / 255.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Scene, Tri2, Vec2};
+
+    #[test]
+    fn u8_and_f64_scenes_render_alike() {
+        let triangle = Tri2::flat(
+            [
+                Vec2 { x: 0.0, y: 0.0 },
+                Vec2 { x: 16.0, y: 0.0 },
+                Vec2 { x: 0.0, y: 16.0 },
+            ],
+            Vec3::new(2.0, 0.5, 0.25),
+        );
+        let mut hdr = Scene::<Vec3>::sized(16, 16);
+        hdr.add_triangle(triangle);
+        hdr.render();
+        let mut bytes = Scene::<[u8; 3]>::sized(16, 16);
+        bytes.add_triangle(triangle);
+        bytes.render();
+
+        for (x, y) in [(2, 2), (14, 14)] {
+            let stored = bytes.pixels()[y * 16 + x];
+            assert_eq!(<[u8; 3]>::from_vec3(hdr.pixel(x, y)), stored);
+            assert_eq!(bytes.pixel(x, y), stored.to_vec3());
+        }
+        // f64 keeps values over 1.0, u8 clamps them
+        assert_eq!(hdr.pixel(2, 2), Vec3::new(2.0, 0.5, 0.25));
+        assert_eq!(bytes.pixels()[2 * 16 + 2], [255, 128, 64]);
+        let expected = Vec3::new(1.0, 128.0 / 255.0, 64.0 / 255.0);
+        assert!((bytes.pixel(2, 2) - expected).length() < 1e-12);
+    }
+}
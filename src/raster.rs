@@ -0,0 +1,1014 @@
+//! rasterizing triangles into the output
+
+use crate::geometry::clip_polygon;
+use crate::lighting::Lighting;
+use crate::{IVec2, Pixel, Shader, Specular, Texture, Tri2, Vec2, Vec3, oit_weight, point_to_bary};
+use rand::prelude::*;
+use std::ops::Range;
+use std::time::Duration;
+use tracing::{Level, event};
+
+/// A 3D triangle projected onto the screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Projected {
+    /// The triangle on the screen.
+    pub(crate) triangle: Tri2,
+    /// The depth of each point.
+    pub(crate) depths: [f64; 3],
+    /// The position of each point in the world.
+    pub(crate) positions: [Vec3; 3],
+    /// The normal at each point.
+    pub(crate) normals: [Vec3; 3],
+    /// How shiny the triangle is.
+    pub(crate) specular: Specular,
+    /// The index of the texture multiplied with the color, if there is one.
+    pub(crate) texture: Option<usize>,
+}
+
+/// Counters from the last render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RenderStats {
+    /// The number of triangles that were rasterized.
+    pub triangles_drawn: usize,
+    /// The number of triangles skipped because they were entirely off-screen or behind the
+    /// camera.
+    pub triangles_culled: usize,
+    /// The number of pixels written (or accumulated, for order-independent transparency).
+    pub pixels_shaded: u64,
+    /// Whether the last render was skipped because nothing had changed. The other counters are
+    /// from the render before it.
+    pub skipped: bool,
+    /// The number of 2D triangles whose edge equations were reused from the render before,
+    /// because the triangles hadn't changed.
+    pub edge_cache_hits: usize,
+    /// How long the last render took, from preparing the frame to the last pixel.
+    pub render_time: Duration,
+    /// The number of triangles left undrawn because [`Scene::render_with_deadline`] ran out of
+    /// time.
+    ///
+    /// [`Scene::render_with_deadline`]: crate::Scene::render_with_deadline
+    pub triangles_over_deadline: usize,
+}
+
+/// Which depths pass the depth test against what's already in the depth buffer. "Less" and
+/// "greater" are in terms of distance from the camera, so they still mean nearer and farther
+/// with a reversed depth range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DepthFunc {
+    /// Pass if nearer.
+    #[default]
+    Less,
+    /// Pass if nearer or at the same depth.
+    LessEqual,
+    /// Pass if farther.
+    Greater,
+    /// Always pass.
+    Always,
+    /// Never pass.
+    Never,
+}
+
+/// Where the samples for multisample antialiasing go in a pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SamplePattern {
+    /// Evenly spaced rows and columns. Edges close to horizontal or vertical only get as many
+    /// shades as there are rows or columns.
+    #[default]
+    Grid,
+    /// The grid turned by atan(1/columns) (about 27° for 4 samples) and wrapped into the pixel,
+    /// so with a square number of samples every sample has its own row and column. With 4
+    /// samples, this is the usual RGSS pattern.
+    RotatedGrid,
+    /// Random positions that are spread out, all kept apart from each other. The same number of
+    /// samples always gets the same positions.
+    Poisson,
+}
+
+impl SamplePattern {
+    /// The positions of `count` samples relative to the center of a pixel, from -0.5 to 0.5.
+    pub fn offsets(self, count: usize) -> Vec<Vec2> {
+        let columns = (count as f64).sqrt().ceil() as usize;
+        let rows = count.div_ceil(columns.max(1));
+        let grid = (0..count).map(|i| Vec2 {
+            x: ((i % columns) as f64 + 0.5) / columns as f64 - 0.5,
+            y: ((i / columns) as f64 + 0.5) / rows as f64 - 0.5,
+        });
+        match self {
+            Self::Grid => grid.collect(),
+            Self::RotatedGrid => {
+                // turning by atan(1/n) and scaling by sqrt(n^2 + 1)/n shifts each row of an n by
+                // n grid by 1/n of a column, e.g. putting a 2x2 grid on (1/8, 3/8)
+                let (cos, sin) = (1.0, 1.0 / columns as f64);
+                let wrap = |value: f64| (value + 0.5).rem_euclid(1.0) - 0.5;
+                grid.map(|offset| Vec2 {
+                    x: wrap(offset.x * cos - offset.y * sin),
+                    y: wrap(offset.x * sin + offset.y * cos),
+                })
+                .collect()
+            }
+            Self::Poisson => {
+                // Mitchell's best candidate: each sample is the candidate farthest from the rest
+                let mut rng = StdRng::seed_from_u64(count as u64);
+                let mut offsets: Vec<Vec2> = Vec::with_capacity(count);
+                for i in 0..count {
+                    let mut candidate = || Vec2 {
+                        x: rng.random::<f64>() - 0.5,
+                        y: rng.random::<f64>() - 0.5,
+                    };
+                    let nearest = |point: Vec2| {
+                        offsets
+                            .iter()
+                            .map(|&other| {
+                                let difference = point - other;
+                                difference.dot(difference)
+                            })
+                            .fold(f64::INFINITY, f64::min)
+                    };
+                    let mut best = candidate();
+                    for _ in 1..(i + 1) * 8 {
+                        let point = candidate();
+                        if nearest(point) > nearest(best) {
+                            best = point;
+                        }
+                    }
+                    offsets.push(best);
+                }
+                offsets
+            }
+        }
+    }
+}
+
+/// What the pixels of triangles show instead of their colors, for finding rendering bugs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DebugMode {
+    /// Show the colors as usual.
+    #[default]
+    Off,
+    /// The barycentric weights as red, green and blue, so each point of a triangle is a pure
+    /// primary color.
+    Barycentric,
+    /// The texture coordinates as red and green.
+    Uv,
+    /// The normals of 3D triangles, mapped from -1.0 to 1.0 onto 0.0 to 1.0. 2D triangles are
+    /// shown as usual.
+    Normals,
+    /// The depths of 3D triangles, from black at the near plane to white at the far plane. 2D
+    /// triangles are shown as usual.
+    Depth,
+}
+
+/// Colors 2D triangles for the debug modes that work without depths or normals.
+struct DebugShader(DebugMode);
+
+impl Shader for DebugShader {
+    fn shade(&self, bary: [f64; 3], _tri: &Tri2, _pixel: Vec2) -> Vec3 {
+        match self.0 {
+            DebugMode::Uv => Vec3::new(bary[1], bary[2], 0.0),
+            _ => Vec3::new(bary[0], bary[1], bary[2]),
+        }
+    }
+}
+
+/// Everything needed to rasterize a frame, prepared once and shared between bands.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Frame {
+    /// The 2D triangles that weren't culled.
+    pub(crate) triangles: Vec<Tri2>,
+    /// The 3D triangles that weren't culled, projected onto the screen.
+    pub(crate) projected: Vec<Projected>,
+    /// The settings to rasterize with.
+    pub(crate) settings: RenderSettings,
+    /// The lights for the 3D triangles, if there are any.
+    pub(crate) lighting: Option<Lighting>,
+    /// The textures the 3D triangles can use.
+    pub(crate) textures: Vec<Texture>,
+}
+
+impl Frame {
+    /// The frame with everything moved by `by` on the screen, except pixel perfect 2D triangles.
+    pub(crate) fn offset(&self, by: Vec2) -> Self {
+        let mut frame = self.clone();
+        for projected in &mut frame.projected {
+            projected.triangle.points = projected.triangle.points.map(|point| point + by);
+        }
+        // pixel perfect triangles stay on whole pixels, so every sample is the same
+        if !self.settings.pixel_perfect {
+            for triangle in &mut frame.triangles {
+                triangle.points = triangle.points.map(|point| point + by);
+            }
+        }
+        frame
+    }
+}
+
+impl Frame {
+    /// The frame with everything scaled by `by` on the screen, so pixel (x, y) of a smaller
+    /// output samples the point (x / by, y / by) of this one.
+    pub(crate) fn scale(&self, by: f64) -> Self {
+        let mut frame = self.clone();
+        for projected in &mut frame.projected {
+            projected.triangle.points = projected.triangle.points.map(|point| point * by);
+        }
+        for triangle in &mut frame.triangles {
+            triangle.points = triangle.points.map(|point| point * by);
+        }
+        frame
+    }
+}
+
+/// The settings that affect rasterization, copied out of the Scene so bands can be rendered on
+/// other threads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct RenderSettings {
+    /// Whether translucent triangles use weighted blended order-independent transparency.
+    pub(crate) oit: bool,
+    /// The depth of the near plane.
+    pub(crate) depth_near: f64,
+    /// The depth of the far plane.
+    pub(crate) depth_far: f64,
+    /// Whether edges are antialiased by their distance from pixels.
+    pub(crate) edge_antialiasing: bool,
+    /// Which depths pass the depth test.
+    pub(crate) depth_func: DepthFunc,
+    /// The depth bias in units of the smallest difference a 24-bit depth buffer can resolve.
+    pub(crate) depth_bias_constant: f64,
+    /// The depth bias as a multiple of a triangle's steepest depth slope.
+    pub(crate) depth_bias_slope: f64,
+    /// The number of samples per pixel, each with its own color and depth. 1 turns
+    /// multisampling off.
+    pub(crate) msaa_samples: usize,
+    /// Where the samples are in each pixel.
+    pub(crate) sample_pattern: SamplePattern,
+    /// What the pixels show instead of their colors.
+    pub(crate) debug_mode: DebugMode,
+    /// Whether 2D triangles are drawn without edge antialiasing or multisampling.
+    pub(crate) pixel_perfect: bool,
+    /// Whether the alpha of 2D triangles decides how many samples they cover with
+    /// multisampling, instead of blending.
+    pub(crate) alpha_to_coverage: bool,
+}
+
+impl RenderSettings {
+    /// Whether a 2D triangle with `alpha` covers `sample` of the pixel at (x, y) with alpha to
+    /// coverage: `alpha` of the samples do, rounded, starting from one that changes from pixel
+    /// to pixel so the pattern doesn't line up.
+    pub(crate) fn alpha_covers(self, alpha: f64, sample: usize, x: usize, y: usize) -> bool {
+        let samples = self.msaa_samples.max(1);
+        let covered = (alpha.clamp(0.0, 1.0) * samples as f64).round() as usize;
+        (sample + x + y) % samples < covered
+    }
+    /// Is depth `a` nearer than depth `b`?
+    pub(crate) fn nearer(self, a: f64, b: f64) -> bool {
+        if self.depth_near > self.depth_far {
+            a > b
+        } else {
+            a < b
+        }
+    }
+    /// Does `depth` pass the depth test against the `stored` depth?
+    pub(crate) fn depth_test(self, depth: f64, stored: f64) -> bool {
+        match self.depth_func {
+            DepthFunc::Less => self.nearer(depth, stored),
+            DepthFunc::LessEqual => !self.nearer(stored, depth),
+            DepthFunc::Greater => self.nearer(stored, depth),
+            DepthFunc::Always => true,
+            DepthFunc::Never => false,
+        }
+    }
+    /// The depth the depth buffer is cleared to, farther than anything.
+    pub(crate) fn farthest(self) -> f64 {
+        if self.depth_near > self.depth_far {
+            f64::NEG_INFINITY
+        } else {
+            f64::INFINITY
+        }
+    }
+    /// The depth bias for a triangle, to add to its depths.
+    pub(crate) fn depth_bias(self, projected: &Projected) -> f64 {
+        if self.depth_bias_constant == 0.0 && self.depth_bias_slope == 0.0 {
+            return 0.0;
+        }
+        let [a, b, c] = projected.triangle.points;
+        let (ab, ac) = (b - a, c - a);
+        let (d_ab, d_ac) = (
+            projected.depths[1] - projected.depths[0],
+            projected.depths[2] - projected.depths[0],
+        );
+        let determinant = ab.x * ac.y - ab.y * ac.x;
+        let max_slope = if determinant == 0.0 {
+            0.0
+        } else {
+            let dx = (d_ab * ac.y - d_ac * ab.y) / determinant;
+            let dy = (d_ac * ab.x - d_ab * ac.x) / determinant;
+            dx.abs().max(dy.abs())
+        };
+        // 24-bit depth buffers can resolve 1/2^24 of the range
+        let resolution = (self.depth_far - self.depth_near) / (1u32 << 24) as f64;
+        let slope_bias = self.depth_bias_slope * max_slope;
+        // pointing away from the camera, whichever way the range goes
+        slope_bias * (self.depth_far - self.depth_near).signum()
+            + self.depth_bias_constant * resolution
+    }
+    /// Is the depth between the near and far planes?
+    pub(crate) fn in_depth_range(self, depth: f64) -> bool {
+        depth >= self.depth_near.min(self.depth_far) && depth <= self.depth_near.max(self.depth_far)
+    }
+}
+
+/// A horizontal band of the output that can be rendered into independently of the others.
+pub(crate) struct Band<'a, P> {
+    /// The colors of the pixels in the band, row by row.
+    colors: &'a mut [P],
+    /// The depths of the pixels in the band, row by row.
+    depths: &'a mut [f64],
+    /// The width of the output.
+    width: usize,
+    /// The Y coordinate of the first row in the band.
+    first_row: usize,
+    /// The edge equations of the frame's 2D triangles, if they've been set up already.
+    edges: Option<&'a [Option<EdgeSetup>]>,
+    /// Which multisampling sample is being drawn, for alpha to coverage, or `None` outside
+    /// [`Band::draw_multisampled`], where there's only the one sample per pixel.
+    sample: Option<usize>,
+    /// The number of pixels shaded in the band so far.
+    pub(crate) pixels_shaded: u64,
+}
+
+impl<'a, P: Pixel> Band<'a, P> {
+    /// A band of rows starting at `first_row`.
+    pub(crate) fn new(
+        colors: &'a mut [P],
+        depths: &'a mut [f64],
+        width: usize,
+        first_row: usize,
+    ) -> Self {
+        Self {
+            colors,
+            depths,
+            width,
+            first_row,
+            edges: None,
+            sample: None,
+            pixels_shaded: 0,
+        }
+    }
+    /// Draw multisampling sample number `sample`.
+    pub(crate) fn with_sample(self, sample: usize) -> Self {
+        Self {
+            sample: Some(sample),
+            ..self
+        }
+    }
+    /// Use `edges` set up ahead of time for the frame's 2D triangles, one for each, instead of
+    /// setting them up while drawing.
+    pub(crate) fn with_edges(self, edges: &'a [Option<EdgeSetup>]) -> Self {
+        Self {
+            edges: Some(edges),
+            ..self
+        }
+    }
+    /// Draw a frame with `shader`: the 3D triangles first, then the 2D triangles on top.
+    pub(crate) fn draw(&mut self, frame: &Frame, shader: &impl Shader) {
+        self.draw_3d(
+            &frame.projected,
+            frame.settings,
+            frame.lighting.as_ref(),
+            &frame.textures,
+            shader,
+        );
+        match frame.settings.debug_mode {
+            mode @ (DebugMode::Barycentric | DebugMode::Uv) => {
+                self.draw_2d(&frame.triangles, frame.settings, &DebugShader(mode))
+            }
+            _ => self.draw_2d(&frame.triangles, frame.settings, shader),
+        }
+    }
+    /// Draw a frame with `shader` once for each multisample antialiasing sample, each starting
+    /// from the band as it is, and average them into the band, keeping the nearest depth of
+    /// each pixel's samples. Without multisampling this is just [`Band::draw`].
+    pub(crate) fn draw_multisampled(&mut self, frame: &Frame, shader: &impl Shader) {
+        let settings = frame.settings;
+        if settings.msaa_samples <= 1 {
+            self.draw(frame, shader);
+            return;
+        }
+        let offsets = settings.sample_pattern.offsets(settings.msaa_samples);
+        let mut colors = vec![Vec3::new(0.0, 0.0, 0.0); self.colors.len()];
+        let mut depths = vec![settings.farthest(); self.depths.len()];
+        for (sample, offset) in offsets.iter().enumerate() {
+            // moving everything the other way puts the sample at the pixel's position
+            let sample_frame = frame.offset(*offset * -1.0);
+            let mut sample_colors = self.colors.to_vec();
+            let mut sample_depths = self.depths.to_vec();
+            let mut band = Band::new(
+                &mut sample_colors,
+                &mut sample_depths,
+                self.width,
+                self.first_row,
+            )
+            .with_sample(sample);
+            band.draw(&sample_frame, shader);
+            self.pixels_shaded += band.pixels_shaded;
+
+            for (sum, color) in colors.iter_mut().zip(&sample_colors) {
+                (*sum) += color.to_vec3();
+            }
+            for (nearest, &depth) in depths.iter_mut().zip(&sample_depths) {
+                if settings.nearer(depth, *nearest) {
+                    (*nearest) = depth;
+                }
+            }
+        }
+
+        let scale = 1.0 / offsets.len() as f64;
+        for (pixel, sum) in self.colors.iter_mut().zip(colors) {
+            (*pixel) = P::from_vec3(sum * scale);
+        }
+        self.depths.copy_from_slice(&depths);
+    }
+    /// The number of rows in the band.
+    fn height(&self) -> usize {
+        self.colors.len().checked_div(self.width).unwrap_or(0)
+    }
+    /// Draw 2D triangles in order, without depth testing.
+    fn draw_2d(&mut self, triangles: &[Tri2], settings: RenderSettings, shader: &impl Shader) {
+        let settings = RenderSettings {
+            edge_antialiasing: settings.edge_antialiasing && !settings.pixel_perfect,
+            ..settings
+        };
+        let (width, first_row, height) = (self.width, self.first_row, self.height());
+        let colors = &mut *self.colors;
+        let pixels_shaded = &mut self.pixels_shaded;
+        // without separate samples to cover, translucent triangles blend as usual
+        let sample = self
+            .sample
+            .filter(|_| settings.alpha_to_coverage && settings.msaa_samples > 1);
+        // the cached edges only match if they're for these triangles
+        let edges = self.edges.filter(|edges| edges.len() == triangles.len());
+        let setup = |i: usize| edges.and_then(|edges| edges[i].as_ref());
+
+        if !settings.oit {
+            for (i, triangle) in triangles.iter().enumerate() {
+                cover(
+                    *triangle,
+                    setup(i),
+                    width,
+                    first_row,
+                    height,
+                    settings,
+                    |x, y, coverage| {
+                        // the samples covered are opaque, so together they blend like alpha
+                        let alpha = match sample {
+                            Some(sample) => {
+                                if !settings.alpha_covers(triangle.alpha, sample, x, y + first_row)
+                                {
+                                    return;
+                                }
+                                coverage
+                            }
+                            None => triangle.alpha * coverage,
+                        };
+                        *pixels_shaded += 1;
+                        let shaded = shade(shader, triangle, x, y + first_row);
+                        let color = &mut colors[y * width + x];
+                        (*color) = P::from_vec3(shaded * alpha + color.to_vec3() * (1.0 - alpha));
+                    },
+                );
+            }
+            return;
+        }
+
+        for (i, triangle) in triangles
+            .iter()
+            .enumerate()
+            .filter(|(_, triangle)| triangle.alpha >= 1.0)
+        {
+            rasterize(*triangle, setup(i), width, first_row, height, |x, y| {
+                *pixels_shaded += 1;
+                colors[y * width + x] = P::from_vec3(shade(shader, triangle, x, y + first_row));
+            });
+        }
+
+        // (sum of weighted colors, sum of weights, revealage) for each pixel
+        let mut accumulated = vec![(Vec3::new(0.0, 0.0, 0.0), 0.0, 1.0); colors.len()];
+        for (i, triangle) in triangles
+            .iter()
+            .enumerate()
+            .filter(|(_, triangle)| triangle.alpha < 1.0)
+        {
+            // 2D triangles are all at depth 0
+            let weight = oit_weight(triangle.alpha, 0.0);
+            rasterize(*triangle, setup(i), width, first_row, height, |x, y| {
+                *pixels_shaded += 1;
+                let shaded = shade(shader, triangle, x, y + first_row);
+                let (color, total_weight, revealage) = &mut accumulated[y * width + x];
+                (*color) += shaded * weight;
+                (*total_weight) += weight;
+                (*revealage) *= 1.0 - triangle.alpha;
+            });
+        }
+
+        for (color, (accumulated, total_weight, revealage)) in colors.iter_mut().zip(accumulated) {
+            if total_weight > 0.0 {
+                (*color) = P::from_vec3(
+                    accumulated * ((1.0 - revealage) / total_weight) + color.to_vec3() * revealage,
+                );
+            }
+        }
+    }
+    /// Draw projected 3D triangles with depth testing, lit per pixel if there's `lighting`.
+    fn draw_3d(
+        &mut self,
+        triangles: &[Projected],
+        settings: RenderSettings,
+        lighting: Option<&Lighting>,
+        textures: &[Texture],
+        shader: &impl Shader,
+    ) {
+        let (width, first_row, height) = (self.width, self.first_row, self.height());
+
+        for projected in triangles {
+            let triangle = projected.triangle;
+            let bias = settings.depth_bias(projected);
+            let texture = projected.texture.and_then(|index| textures.get(index));
+            cover(
+                triangle,
+                None,
+                width,
+                first_row,
+                height,
+                settings,
+                |x, y, coverage| {
+                    let pixel = Vec2 {
+                        x: x as f64,
+                        y: (y + first_row) as f64,
+                    };
+                    let Some(weights) = point_to_bary(triangle.points, pixel) else {
+                        return;
+                    };
+                    let depth = (0..3)
+                        .map(|i| projected.depths[i] * weights[i])
+                        .sum::<f64>()
+                        + bias;
+                    let index = y * width + x;
+
+                    if settings.in_depth_range(depth)
+                        && settings.depth_test(depth, self.depths[index])
+                    {
+                        self.pixels_shaded += 1;
+                        // partly covered edge pixels blend over what's behind them without hiding it
+                        if coverage >= 0.5 {
+                            self.depths[index] = depth;
+                        }
+                        let interpolate = |values: [Vec3; 3]| {
+                            values[0] * weights[0] + values[1] * weights[1] + values[2] * weights[2]
+                        };
+                        let uv = Vec2 {
+                            x: weights[1],
+                            y: weights[2],
+                        };
+                        let shaded = match settings.debug_mode {
+                            DebugMode::Off => {
+                                let mut shaded = shader.shade(weights, &triangle, pixel);
+                                if let Some(texture) = texture {
+                                    shaded = shaded * texture.sample(uv);
+                                }
+                                match lighting {
+                                    Some(lighting) => lighting.shade(
+                                        shaded,
+                                        interpolate(projected.normals),
+                                        interpolate(projected.positions),
+                                        projected.specular,
+                                    ),
+                                    None => shaded,
+                                }
+                            }
+                            DebugMode::Barycentric => Vec3::new(weights[0], weights[1], weights[2]),
+                            DebugMode::Uv => Vec3::new(uv.x, uv.y, 0.0),
+                            DebugMode::Normals => {
+                                interpolate(projected.normals).normalize() * 0.5
+                                    + Vec3::new(0.5, 0.5, 0.5)
+                            }
+                            DebugMode::Depth => {
+                                let depth = ((depth - settings.depth_near)
+                                    / (settings.depth_far - settings.depth_near))
+                                    .clamp(0.0, 1.0);
+                                Vec3::new(depth, depth, depth)
+                            }
+                        };
+                        self.colors[index] = P::from_vec3(
+                            shaded * coverage + self.colors[index].to_vec3() * (1.0 - coverage),
+                        );
+                    }
+                },
+            );
+        }
+    }
+}
+
+/// How much of each pixel of a `width` by `height` output is covered by the triangles of the
+/// frame, from 0.0 to 1.0, row by row.
+pub(crate) fn coverage_mask(frame: &Frame, width: usize, height: usize) -> Vec<f64> {
+    let mut mask = vec![0.0; width * height];
+    let triangles = frame
+        .projected
+        .iter()
+        .map(|projected| projected.triangle)
+        .chain(frame.triangles.iter().copied());
+    for triangle in triangles {
+        cover(
+            triangle,
+            None,
+            width,
+            0,
+            height,
+            frame.settings,
+            |x, y, coverage| {
+                let covered = &mut mask[y * width + x];
+                (*covered) += coverage * (1.0 - *covered);
+            },
+        );
+    }
+    mask
+}
+
+/// Color the pixel at (x, y) inside a 2D triangle with `shader`.
+fn shade(shader: &impl Shader, triangle: &Tri2, x: usize, y: usize) -> Vec3 {
+    let pixel = Vec2 {
+        x: x as f64,
+        y: y as f64,
+    };
+    // pixels inside degenerate triangles are all at the first point
+    let weights = point_to_bary(triangle.points, pixel).unwrap_or([1.0, 0.0, 0.0]);
+    shader.shade(weights, triangle, pixel)
+}
+
+/// The columns and rows of the pixels from `top_left` to `bottom_right` (inclusive), clipped to
+/// the band of `height` rows of `width` pixels starting at `first_row`.
+pub(crate) fn clip_to_band(
+    top_left: IVec2,
+    bottom_right: IVec2,
+    width: usize,
+    first_row: usize,
+    height: usize,
+) -> (Range<usize>, Range<usize>) {
+    /// The first pixel at or after `start`, clipped to 0..`end`.
+    fn clip(start: i32, end: usize) -> usize {
+        (start.max(0) as usize).min(end)
+    }
+    let xs = clip(top_left.x, width)..clip(bottom_right.x.saturating_add(1), width);
+    let ys = clip(top_left.y, first_row + height).max(first_row)
+        ..clip(bottom_right.y.saturating_add(1), first_row + height);
+    (xs, ys)
+}
+
+/// The number of fractional bits in the fixed-point coordinates used by [`rasterize`].
+const SUBPIXEL_BITS: u32 = 8;
+
+/// Snap a point to fixed-point coordinates with [`SUBPIXEL_BITS`] fractional bits, clamped so
+/// the edge functions can't overflow.
+fn to_fixed(point: Vec2) -> [i64; 2] {
+    /// The largest fixed-point coordinate, far outside any output.
+    const LIMIT: i64 = 1 << 48;
+    let scale = (1 << SUBPIXEL_BITS) as f64;
+    [point.x, point.y].map(|value| ((value * scale).round() as i64).clamp(-LIMIT, LIMIT))
+}
+
+/// Twice the signed area of the triangle (a, b, p), positive when p is to the right of a -> b
+/// (with Y pointing down).
+fn edge_function(a: [i64; 2], b: [i64; 2], p: [i64; 2]) -> i128 {
+    (b[0] - a[0]) as i128 * (p[1] - a[1]) as i128 - (b[1] - a[1]) as i128 * (p[0] - a[0]) as i128
+}
+
+/// A triangle set up for [`rasterize`]: its points snapped to fixed-point coordinates, its
+/// edges, and the pixels it could cover.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EdgeSetup {
+    /// The edges, clockwise on the screen (with Y pointing down).
+    edges: [([i64; 2], [i64; 2]); 3],
+    /// What to add to each edge function so pixels exactly on the edge are only inside for top
+    /// and left edges.
+    biases: [i128; 3],
+    /// The first whole pixel at or after the top-left of the bounding box.
+    top_left: IVec2,
+    /// The last whole pixel at or before the bottom-right of the bounding box.
+    bottom_right: IVec2,
+}
+
+impl EdgeSetup {
+    /// Set up a triangle, or `None` if it can't cover anything because it has no area or
+    /// non-finite points.
+    pub(crate) fn new(triangle: Tri2) -> Option<Self> {
+        if !triangle.is_finite() {
+            return None;
+        }
+        let [a, mut b, mut c] = triangle.points.map(to_fixed);
+        match edge_function(a, b, c).signum() {
+            0 => return None,
+            -1 => std::mem::swap(&mut b, &mut c),
+            _ => {}
+        }
+        let edges = [(a, b), (b, c), (c, a)];
+        let biases = edges.map(|(from, to)| fill_rule_bias(from, to));
+
+        let (min, max) = (
+            [0, 1].map(|i| a[i].min(b[i]).min(c[i])),
+            [0, 1].map(|i| a[i].max(b[i]).max(c[i])),
+        );
+        let scale = (1 << SUBPIXEL_BITS) as f64;
+        // the first whole pixel at or after the minimum, and the last at or before the maximum
+        let top_left = Vec2 {
+            x: min[0] as f64 / scale,
+            y: min[1] as f64 / scale,
+        }
+        .ceil_to_ivec2();
+        let bottom_right = Vec2 {
+            x: max[0] as f64 / scale,
+            y: max[1] as f64 / scale,
+        }
+        .floor_to_ivec2();
+        event!(
+            Level::TRACE,
+            "calculated triangle bounding box: {top_left:?}, {bottom_right:?}"
+        );
+        Some(Self {
+            edges,
+            biases,
+            top_left,
+            bottom_right,
+        })
+    }
+    /// Call `plot` with the position of every pixel inside the triangle, like [`rasterize`].
+    fn plot_pixels(
+        &self,
+        width: usize,
+        first_row: usize,
+        height: usize,
+        mut plot: impl FnMut(usize, usize),
+    ) {
+        /// The most pixels a triangle sticking out of the band can have in its (clipped) bounding
+        /// box before it's cut down to the band first.
+        const CLIP_AREA: usize = 64 * 64;
+
+        let (xs, ys) = clip_to_band(self.top_left, self.bottom_right, width, first_row, height);
+        let sticks_out = self.top_left.x < 0
+            || self.top_left.y < first_row as i32
+            || self.bottom_right.x >= width as i32
+            || self.bottom_right.y >= (first_row + height) as i32;
+        if sticks_out && xs.len() * ys.len() > CLIP_AREA {
+            self.plot_clipped(width, first_row, height, plot);
+            return;
+        }
+        for y in ys {
+            for x in xs.clone() {
+                let p = [(x as i64) << SUBPIXEL_BITS, (y as i64) << SUBPIXEL_BITS];
+                let inside = self
+                    .edges
+                    .iter()
+                    .zip(self.biases)
+                    .all(|(&(from, to), bias)| edge_function(from, to, p) + bias >= 0);
+                if inside {
+                    plot(x, y - first_row);
+                }
+            }
+        }
+    }
+    /// Like [`EdgeSetup::plot_pixels`], but cut the triangle down to the band first, so huge
+    /// triangles only test the pixels near the parts of them in the band.
+    ///
+    /// The pieces are a fan of triangles that each test the pixels in their own bounding box
+    /// against the whole triangle's edges and the diagonals between the pieces. The diagonals are
+    /// shared exactly, so the pieces cover the same pixels as the whole triangle, once each.
+    fn plot_clipped(
+        &self,
+        width: usize,
+        first_row: usize,
+        height: usize,
+        mut plot: impl FnMut(usize, usize),
+    ) {
+        let scale = (1 << SUBPIXEL_BITS) as f64;
+        let corners = self.edges.map(|(from, _)| Vec2 {
+            x: from[0] as f64 / scale,
+            y: from[1] as f64 / scale,
+        });
+        // a pixel outside the band, so the cut edges don't pass through any pixels in it
+        let (min, max) = (
+            Vec2 {
+                x: -1.0,
+                y: first_row as f64 - 1.0,
+            },
+            Vec2 {
+                x: width as f64,
+                y: (first_row + height) as f64,
+            },
+        );
+        let mut polygon = clip_polygon(&corners, min, max)
+            .into_iter()
+            .map(to_fixed)
+            .collect::<Vec<[i64; 2]>>();
+        polygon.dedup();
+        if polygon.len() > 1 && polygon.first() == polygon.last() {
+            polygon.pop();
+        }
+        // fanning out from a corner outside the band keeps the one point every piece shares off
+        // the pixels in it
+        let off_band = |point: &[i64; 2]| {
+            point[0] < 0
+                || point[0] > (width as i64 - 1) << SUBPIXEL_BITS
+                || point[1] < (first_row as i64) << SUBPIXEL_BITS
+                || point[1] > ((first_row + height) as i64 - 1) << SUBPIXEL_BITS
+        };
+        let Some(apex) = polygon.iter().position(off_band) else {
+            return;
+        };
+        polygon.rotate_left(apex);
+
+        let last = polygon.len().saturating_sub(1);
+        for i in 1..last {
+            let (a, b, c) = (polygon[0], polygon[i], polygon[i + 1]);
+            let diagonals = [(i >= 2).then_some((a, b)), (i + 1 < last).then_some((c, a))];
+            let (min, max) = (
+                [0, 1].map(|j| a[j].min(b[j]).min(c[j])),
+                [0, 1].map(|j| a[j].max(b[j]).max(c[j])),
+            );
+            // a pixel of leeway, since the pieces' corners are snapped
+            let (xs, ys) = clip_to_band(
+                IVec2 {
+                    x: (min[0] >> SUBPIXEL_BITS) as i32 - 1,
+                    y: (min[1] >> SUBPIXEL_BITS) as i32 - 1,
+                },
+                IVec2 {
+                    x: (max[0] >> SUBPIXEL_BITS) as i32 + 2,
+                    y: (max[1] >> SUBPIXEL_BITS) as i32 + 2,
+                },
+                width,
+                first_row,
+                height,
+            );
+            for y in ys {
+                for x in xs.clone() {
+                    let p = [(x as i64) << SUBPIXEL_BITS, (y as i64) << SUBPIXEL_BITS];
+                    let inside = self
+                        .edges
+                        .iter()
+                        .zip(self.biases)
+                        .all(|(&(from, to), bias)| edge_function(from, to, p) + bias >= 0)
+                        && diagonals.iter().flatten().all(|&(from, to)| {
+                            edge_function(from, to, p) + fill_rule_bias(from, to) >= 0
+                        });
+                    if inside {
+                        plot(x, y - first_row);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// What to add to the edge function of the edge from `from` to `to` so pixels exactly on it are
+/// only inside for top edges (flat, interior below) and left edges (going up).
+fn fill_rule_bias(from: [i64; 2], to: [i64; 2]) -> i128 {
+    let top_left = (from[1] == to[1] && to[0] > from[0]) || to[1] < from[1];
+    if top_left { 0 } else { -1 }
+}
+
+/// Call `plot` with the position of every pixel inside the triangle, clipped to the band of
+/// `height` rows of `width` pixels starting at `first_row`. The Y position passed is relative
+/// to the band. Triangles with non-finite points cover nothing.
+///
+/// The points are snapped to fixed-point coordinates and tested with integer edge functions,
+/// with pixels exactly on an edge only counted for top and left edges, so triangles sharing an
+/// edge cover every pixel along it exactly once. The triangle's `setup` is used if it's been set
+/// up already.
+fn rasterize(
+    triangle: Tri2,
+    setup: Option<&EdgeSetup>,
+    width: usize,
+    first_row: usize,
+    height: usize,
+    plot: impl FnMut(usize, usize),
+) {
+    match setup {
+        Some(setup) => setup.plot_pixels(width, first_row, height, plot),
+        None => {
+            if let Some(setup) = EdgeSetup::new(triangle) {
+                setup.plot_pixels(width, first_row, height, plot);
+            }
+        }
+    }
+}
+
+/// Call `plot` with the position and coverage (from 0.0 to 1.0) of every pixel the triangle
+/// covers, like [`rasterize`]. With edge antialiasing, pixels near the edges are partly covered
+/// depending on their distance from the edge, otherwise coverage is always 1.0. Without edge
+/// antialiasing, the triangle's `setup` is used if it's been set up already.
+fn cover(
+    triangle: Tri2,
+    setup: Option<&EdgeSetup>,
+    width: usize,
+    first_row: usize,
+    height: usize,
+    settings: RenderSettings,
+    mut plot: impl FnMut(usize, usize, f64),
+) {
+    if !settings.edge_antialiasing {
+        rasterize(triangle, setup, width, first_row, height, |x, y| {
+            plot(x, y, 1.0)
+        });
+        return;
+    }
+    if triangle.is_degenerate() || !triangle.is_finite() {
+        return;
+    }
+
+    let (top_left, bottom_right) = triangle.bounding_box();
+    let (xs, ys) = clip_to_band(
+        (top_left - Vec2 { x: 1.0, y: 1.0 }).floor_to_ivec2(),
+        (bottom_right + Vec2 { x: 1.0, y: 1.0 }).floor_to_ivec2(),
+        width,
+        first_row,
+        height,
+    );
+
+    for y in ys {
+        for x in xs.clone() {
+            let distance = triangle.signed_distance(Vec2 {
+                x: x as f64,
+                y: y as f64,
+            });
+            // pixels are 1 unit wide
+            let coverage = (0.5 + distance).clamp(0.0, 1.0);
+            if coverage > 0.0 {
+                plot(x, y - first_row, coverage);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_of_triangles_covers_every_pixel_once() {
+        let (width, height) = (40, 30);
+        // a jittered grid of points reaching past the edges of the output
+        let point = |i: usize, j: usize| Vec2 {
+            x: -3.0 + i as f64 * 4.7 + (i * 7 + j * 3) as f64 % 1.3,
+            y: -2.0 + j as f64 * 3.9 + (i * 5 + j * 11) as f64 % 0.9,
+        };
+        let mut counts = vec![0; width * height];
+        for j in 0..10 {
+            for i in 0..10 {
+                let [a, b, c, d] = [
+                    point(i, j),
+                    point(i + 1, j),
+                    point(i + 1, j + 1),
+                    point(i, j + 1),
+                ];
+                for points in [[a, b, c], [a, c, d]] {
+                    let triangle = Tri2::flat(points, Vec3::new(1.0, 1.0, 1.0));
+                    rasterize(triangle, None, width, 0, height, |x, y| {
+                        counts[y * width + x] += 1;
+                    });
+                }
+            }
+        }
+        assert!(counts.iter().all(|&count| count == 1));
+    }
+
+    #[test]
+    fn sample_patterns_for_four_samples() {
+        let positions = |pattern: SamplePattern| {
+            pattern
+                .offsets(4)
+                .iter()
+                .map(|offset| (offset.x, offset.y))
+                .collect::<Vec<(f64, f64)>>()
+        };
+        assert_eq!(
+            positions(SamplePattern::Grid),
+            [(-0.25, -0.25), (0.25, -0.25), (-0.25, 0.25), (0.25, 0.25)]
+        );
+        assert_eq!(
+            positions(SamplePattern::RotatedGrid),
+            [
+                (-0.125, -0.375),
+                (0.375, -0.125),
+                (-0.375, 0.125),
+                (0.125, 0.375)
+            ]
+        );
+
+        let poisson = positions(SamplePattern::Poisson);
+        assert_eq!(poisson, positions(SamplePattern::Poisson));
+        assert_eq!(poisson.len(), 4);
+        for (i, &(x, y)) in poisson.iter().enumerate() {
+            assert!((-0.5..0.5).contains(&x) && (-0.5..0.5).contains(&y));
+            for &(other_x, other_y) in &poisson[..i] {
+                assert!((x - other_x).hypot(y - other_y) > 0.2);
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! custom per-pixel coloring
+
+use crate::{Tri2, Vec2, Vec3};
+
+/// Colors the pixels covered by triangles.
+pub trait Shader {
+    /// The color of `pixel`, at barycentric weights `bary` inside `tri`.
+    fn shade(&self, bary: [f64; 3], tri: &Tri2, pixel: Vec2) -> Vec3;
+}
+
+/// The default shader, interpolating the colors at the points of the triangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FlatShader;
+
+impl Shader for FlatShader {
+    fn shade(&self, bary: [f64; 3], tri: &Tri2, _pixel: Vec2) -> Vec3 {
+        if tri.is_flat() {
+            return tri.colors[0];
+        }
+        let [u, v, w] = bary;
+        tri.colors[0] * u + tri.colors[1] * v + tri.colors[2] * w
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Scene;
+
+    /// Colors each pixel with its barycentric weights.
+    struct BaryShader;
+
+    impl Shader for BaryShader {
+        fn shade(&self, bary: [f64; 3], _tri: &Tri2, _pixel: Vec2) -> Vec3 {
+            Vec3::new(bary[0], bary[1], bary[2])
+        }
+    }
+
+    #[test]
+    fn barycentric_shader_is_gray_at_the_centroid() {
+        let mut scene = Scene::<Vec3>::sized(30, 30);
+        scene.add_triangle(Tri2::flat(
+            [
+                Vec2 { x: 0.0, y: 0.0 },
+                Vec2 { x: 30.0, y: 0.0 },
+                Vec2 { x: 0.0, y: 30.0 },
+            ],
+            Vec3::new(1.0, 0.0, 0.0),
+        ));
+        scene.render_with_shader(&BaryShader);
+        let centroid = scene.pixel(10, 10);
+        let gray = Vec3::new(1.0, 1.0, 1.0) * (1.0 / 3.0);
+        assert!((centroid - gray).length() < 0.05);
+
+        scene.clear_color_buffer();
+        scene.render_with_shader(&FlatShader);
+        assert_eq!(scene.pixel(10, 10), Vec3::new(1.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,294 @@
+//! textures, and rendering into them
+
+use crate::raster::Band;
+use crate::{FlatShader, Pixel, Scene, Vec2, Vec3};
+
+/// What happens to UV coordinates outside 0.0 to 1.0 when sampling a [`Texture`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AddressMode {
+    /// Use the texel at the nearest edge.
+    #[default]
+    Clamp,
+    /// Tile the texture, so 1.25 is the same as 0.25.
+    Repeat,
+    /// Tile the texture, flipping every other tile, so 1.25 is the same as 0.75.
+    Mirror,
+}
+
+impl AddressMode {
+    /// Bring a coordinate into 0.0 to 1.0.
+    pub fn apply(self, coordinate: f64) -> f64 {
+        match self {
+            Self::Clamp => coordinate.clamp(0.0, 1.0),
+            Self::Repeat => coordinate.rem_euclid(1.0),
+            Self::Mirror => {
+                let coordinate = coordinate.rem_euclid(2.0);
+                if coordinate > 1.0 {
+                    2.0 - coordinate
+                } else {
+                    coordinate
+                }
+            }
+        }
+    }
+}
+
+/// An image that can be sampled with UV coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Texture {
+    /// The width in texels.
+    width: usize,
+    /// The height in texels.
+    height: usize,
+    /// The colors of the texels, row by row.
+    texels: Vec<Vec3>,
+    /// What happens to U coordinates outside 0.0 to 1.0.
+    pub address_u: AddressMode,
+    /// What happens to V coordinates outside 0.0 to 1.0.
+    pub address_v: AddressMode,
+}
+
+impl Texture {
+    /// Create a texture from its texels, row by row, that clamps UVs to its edges. Returns `None`
+    /// if there aren't `width * height` texels, or there are none.
+    pub fn new(width: usize, height: usize, texels: Vec<Vec3>) -> Option<Self> {
+        (width > 0 && height > 0 && texels.len() == width * height).then_some(Self {
+            width,
+            height,
+            texels,
+            address_u: AddressMode::default(),
+            address_v: AddressMode::default(),
+        })
+    }
+    /// The width in texels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    /// The height in texels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    /// The color of the texel at (x, y).
+    pub fn texel(&self, x: usize, y: usize) -> Vec3 {
+        self.texels[y * self.width + x]
+    }
+    /// The color of the nearest texel to `uv`, where (0, 0) is the top-left corner of the
+    /// texture and (1, 1) is the bottom-right. UVs outside that range are handled by
+    /// [`Texture::address_u`] and [`Texture::address_v`].
+    pub fn sample(&self, uv: Vec2) -> Vec3 {
+        /// The texel a coordinate from 0.0 to 1.0 falls in, out of `size`.
+        fn texel(coordinate: f64, size: usize) -> usize {
+            ((coordinate * size as f64).floor().max(0.0) as usize).min(size - 1)
+        }
+        self.texel(
+            texel(self.address_u.apply(uv.x), self.width),
+            texel(self.address_v.apply(uv.y), self.height),
+        )
+    }
+}
+
+/// An off-screen color buffer, and optionally a depth buffer, for a Scene to render into (see
+/// [`Scene::render_to`]) so the result can be used as a [`Texture`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderTarget {
+    /// The width in pixels.
+    width: usize,
+    /// The height in pixels.
+    height: usize,
+    /// The colors of the pixels, row by row.
+    color: Vec<Vec3>,
+    /// The depths of the pixels, row by row, if the target keeps them.
+    depth: Option<Vec<f64>>,
+    /// The farthest depth the depth buffer was filled with, so it can be filled again when a
+    /// Scene with its depth test the other way around renders into it.
+    farthest: f64,
+}
+
+impl RenderTarget {
+    /// Create a black render target with a depth buffer. It starts out at the farthest depth of
+    /// whichever Scene renders into it first (see [`Scene::set_reversed_z`]).
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            depth: Some(vec![f64::INFINITY; width * height]),
+            ..Self::without_depth(width, height)
+        }
+    }
+    /// Create a black render target without a depth buffer. 3D triangles are still depth tested
+    /// against each other while rendering, but the depths are thrown away afterwards.
+    pub fn without_depth(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            color: vec![Vec3::new(0.0, 0.0, 0.0); width * height],
+            depth: None,
+            farthest: f64::INFINITY,
+        }
+    }
+    /// The width in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    /// The height in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    /// The color of the pixel at (x, y).
+    pub fn pixel(&self, x: usize, y: usize) -> Vec3 {
+        self.color[y * self.width + x]
+    }
+    /// The colors of all the pixels, row by row.
+    pub fn pixels(&self) -> &[Vec3] {
+        &self.color
+    }
+    /// The depth buffer, row by row, if the target has one.
+    pub fn depth(&self) -> Option<&[f64]> {
+        self.depth.as_deref()
+    }
+    /// Copy the colors into a texture for sampling. Returns `None` if the target is empty.
+    pub fn as_texture(&self) -> Option<Texture> {
+        Texture::new(self.width, self.height, self.color.clone())
+    }
+}
+
+impl<P: Pixel> Scene<P> {
+    /// Render this Scene into `target` instead of the output, e.g. for a shadow map or a
+    /// reflection. The camera's view is stretched over the whole target, like with the output,
+    /// and 2D triangles are drawn at their pixel coordinates. The target is cleared first if
+    /// [`Scene::clear_on_render`] is set. This doesn't touch the Scene's output, buffers or
+    /// [`Scene::stats`].
+    pub fn render_to(&self, target: &mut RenderTarget) {
+        let (mut frame, _) = self.prepare_frame();
+        // the projection maps the camera's view onto the output, so stretch it onto the target
+        let scale = Vec2 {
+            x: target.width as f64 / self.width as f64,
+            y: target.height as f64 / self.height as f64,
+        };
+        for projected in &mut frame.projected {
+            for point in &mut projected.triangle.points {
+                (*point) = (*point) * scale;
+            }
+        }
+        // 2D triangles off the output can still be on a bigger target
+        frame.triangles = self
+            .triangles
+            .iter()
+            .filter(|triangle| triangle.is_finite())
+            .map(|&triangle| self.to_screen(triangle))
+            .collect();
+
+        let farthest = frame.settings.farthest();
+        if self.clear_on_render {
+            for (i, color) in target.color.iter_mut().enumerate() {
+                (*color) = Self::background_color(
+                    self.background(),
+                    self.clear_color,
+                    i % target.width,
+                    i / target.width,
+                    target.width,
+                    target.height,
+                );
+            }
+        }
+        // depths from the other direction would all be nearer than anything new
+        let turned_around = target.farthest != farthest;
+        target.farthest = farthest;
+        let mut scratch_depth;
+        let depth = match &mut target.depth {
+            Some(depth) => {
+                if self.clear_on_render || turned_around {
+                    depth.fill(farthest);
+                }
+                depth
+            }
+            None => {
+                scratch_depth = vec![farthest; target.width * target.height];
+                &mut scratch_depth
+            }
+        };
+        Band::new(&mut target.color, depth, target.width, 0).draw_multisampled(&frame, &FlatShader);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Tri2, Tri3};
+
+    #[test]
+    fn render_to_a_small_target() {
+        let mut scene = Scene::<Vec3>::sized(40, 40);
+        let (red, green) = (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        scene.add_triangle(Tri2::flat(
+            [
+                Vec2 { x: 0.0, y: 0.0 },
+                Vec2 { x: 6.0, y: 0.0 },
+                Vec2 { x: 0.0, y: 6.0 },
+            ],
+            red,
+        ));
+        scene.add_triangle_3d(Tri3 {
+            points: [
+                Vec3::new(-1.0, -1.0, 0.0),
+                Vec3::new(1.0, -1.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            ],
+            color: green,
+            material: 0,
+        });
+        let mut target = RenderTarget::new(10, 10);
+        scene.render_to(&mut target);
+        assert_eq!(target.pixel(1, 1), red);
+        // the 3D triangle in the middle of the view is stretched onto the middle of the target
+        assert_eq!(target.pixel(5, 5), green);
+        assert!(target.depth().unwrap()[5 * 10 + 5].is_finite());
+        assert_eq!(target.pixel(9, 0), Vec3::new(0.0, 0.0, 0.0));
+        // the Scene's own output is untouched
+        assert_eq!(scene.pixel(1, 1), Vec3::new(0.0, 0.0, 0.0));
+
+        let texture = target.as_texture().unwrap();
+        assert_eq!(texture.sample(Vec2 { x: 0.15, y: 0.15 }), red);
+    }
+
+    #[test]
+    fn repeat_and_mirror_wrap_uvs() {
+        let texels = (0..4)
+            .map(|i| Vec3::new(i as f64, 0.0, 0.0))
+            .collect::<Vec<Vec3>>();
+        let mut texture = Texture::new(4, 1, texels).unwrap();
+        let at = |texture: &Texture, u| texture.sample(Vec2 { x: u, y: 0.5 });
+        assert_eq!(at(&texture, 1.25), at(&texture, 1.0));
+        texture.address_u = AddressMode::Repeat;
+        assert_eq!(at(&texture, 1.25), at(&texture, 0.25));
+        texture.address_u = AddressMode::Mirror;
+        assert_eq!(at(&texture, 1.25), at(&texture, 0.75));
+        // the axes are independent
+        assert_eq!(texture.address_v, AddressMode::Clamp);
+    }
+
+    #[test]
+    fn reversed_z_scenes_draw_into_new_targets() {
+        let mut scene = Scene::<Vec3>::sized(40, 40);
+        scene.set_reversed_z(true);
+        let green = Vec3::new(0.0, 1.0, 0.0);
+        scene.add_triangle_3d(Tri3 {
+            points: [
+                Vec3::new(-1.0, -1.0, 0.0),
+                Vec3::new(1.0, -1.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            ],
+            color: green,
+            material: 0,
+        });
+        let mut target = RenderTarget::new(10, 10);
+        scene.render_to(&mut target);
+        assert_eq!(target.pixel(5, 5), green);
+        assert_eq!(target.depth().unwrap()[0], f64::NEG_INFINITY);
+
+        // and back the usual way around
+        scene.set_reversed_z(false);
+        scene.render_to(&mut target);
+        assert_eq!(target.depth().unwrap()[0], f64::INFINITY);
+        assert!(target.depth().unwrap()[5 * 10 + 5].is_finite());
+    }
+}